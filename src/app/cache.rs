@@ -43,7 +43,7 @@ impl<T> Cache<T> {
 
     pub fn set(&mut self, name: &'static str, value: T) -> Option<()> {
         if let Some(i) = self.keys.iter().position(|w| w == name) {
-            self.buf[i] = Box::new(value);
+            *self.buf[i] = value;
             Some(())
         } else {
             None
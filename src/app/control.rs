@@ -0,0 +1,38 @@
+use crate::scene::{SceneKey, SceneKeyT};
+use std::fmt;
+
+// What the app does next, returned by run, bound action handlers and timers
+pub enum Control {
+    Continue,
+    // Ends the app, start returns the code
+    Quit(usize),
+    // Makes the scene with the key current, it's drawn on this frame already
+    Switch(Box<dyn SceneKey>),
+}
+
+impl Control {
+    pub fn switch(key: impl SceneKeyT) -> Control {
+        Control::Switch(Box::new(key))
+    }
+}
+
+impl PartialEq for Control {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Control::Continue, Control::Continue) => true,
+            (Control::Quit(a), Control::Quit(b)) => a == b,
+            (Control::Switch(a), Control::Switch(b)) => SceneKey::eq(a.as_ref(), b.as_ref()),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for Control {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Control::Continue => write!(f, "Continue"),
+            Control::Quit(code) => write!(f, "Quit({code})"),
+            Control::Switch(_) => write!(f, "Switch"),
+        }
+    }
+}
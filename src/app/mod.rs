@@ -1,176 +1,760 @@
 mod cache;
+pub mod control;
 pub mod option;
+pub mod program;
+mod signal;
 pub(crate) mod terminal;
+pub mod timer;
+pub mod wake;
 
 use crate::{
-    draw::cursor,
-    input::Input,
-    panel::{Panel, frame::Frame},
-    scene::{DefaultScene, SceneHandler, SceneKey, SceneKeyT},
-    style::{self, PrintableStyle, set_style},
-    widget::{Widget, attr::Attr},
+    backend::{Backend, ansi::AnsiBackend, headless::HeadlessBackend},
+    draw::{buffer::Buffer, cursor, screen::Screen},
+    input::{
+        Input,
+        binds::{Action, Bind},
+        key::{Key, KeyState, Protocol},
+        modifier::Modifier,
+        mouse::{ClickCounter, MouseState},
+        sequence::{Sequence, SequenceEvent, SequenceMatcher},
+    },
+    panel::{Panel, focus::Direction, frame::Frame},
+    scene::{DefaultScene, SceneHandler, SceneKey},
+    unit::Point,
+    widget::{
+        Widget,
+        attr::{Attr, Dirty},
+    },
 };
 use cache::*;
-use libc::{SIGINT, sighandler_t, signal};
+use control::Control;
 use option::*;
+use signal::Interrupt;
 use std::{
     any::{Any, TypeId},
+    cell::{Cell, RefCell},
     collections::HashMap,
-    time::Instant,
+    io,
+    process::{Command, ExitStatus},
+    rc::Rc,
+    time::{Duration, Instant},
 };
 use terminal::{Terminal, termsz};
+use timer::{TimerCallback, TimerId, Timers};
+use wake::{WakePipe, Waker};
 
+thread_local! {
+    // Size of whatever is being rendered to, set while a headless app or frame is drawn to
+    static VIEWPORT: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+// App lifecycle delegates
+type Hook<S> = Box<dyn FnMut(&mut App<S>)>;
+type Run<S> = Box<dyn FnMut(&mut App<S>, Option<Input>) -> Control>;
+type ResizeHook<S> = Box<dyn FnMut(&mut App<S>, usize, usize)>;
+
+type Handler<S> = Rc<RefCell<dyn FnMut(&mut App<S>, &dyn Action) -> Control>>;
+
+// Whether a handler was registered for the bind's action
+fn handled<S>(bind: &Bind, handlers: &HashMap<TypeId, Handler<S>>) -> bool {
+    bind.action
+        .as_ref()
+        .is_some_and(|a| handlers.contains_key(&Any::type_id(a.as_any())))
+}
+
+// Size used for percentage units, the terminal size unless rendering to another backend
 pub(crate) fn get_tsz() -> (usize, usize) {
-    termsz()
+    VIEWPORT.with(|v| v.get()).unwrap_or_else(termsz)
 }
 
-extern "C" fn handle_sigint(_: i32) {}
+// Sets the size percentages resolve against until dropped, then puts back the one before
+pub(crate) struct Viewport(Option<(usize, usize)>);
+
+impl Viewport {
+    pub(crate) fn set(size: Option<(usize, usize)>) -> Viewport {
+        Viewport(VIEWPORT.with(|v| v.replace(size)))
+    }
+}
+
+impl Drop for Viewport {
+    fn drop(&mut self) {
+        VIEWPORT.with(|v| v.set(self.0));
+    }
+}
 
-pub struct App {
-    term: Terminal,
+pub struct App<S = ()> {
+    // Restores the terminal when dropped. None when headless.
+    term: Option<Terminal>,
+    screen: Screen<Box<dyn Backend>>,
+    // Woken by wakers from other threads while waiting for input
+    wake: WakePipe,
+    timers: Timers<S>,
+    // Start of the last step, for stepping at a fixed rate
+    last_step: Instant,
+    // Whether the terminal window has focus, going by the last focus event
+    focused: bool,
+    // For double and triple clicks
+    clicks: ClickCounter,
+    // Keys pressed so far of a bound sequence
+    sequences: SequenceMatcher,
+    // Scene drawn last, another one has to be drawn from scratch
+    drawn_scene: Option<usize>,
+    // Scene index, path and tag of the focused widget last reported to run
+    last_focus: Option<(usize, Vec<usize>, Option<String>)>,
+    // Set once something returned Control::Quit
+    exit_code: Option<usize>,
+    // Whatever the app keeps track of, handed to every delegate through the app
+    pub state: S,
     // Options
     pub opts: AppOptions,
     // Scenes
     pub scenes: SceneHandler,
-    // App lifecycle delegates
-    pub init: fn(&mut Self),
-    pub run: fn(&mut Self, Option<Input>) -> Option<usize>,
-    pub end: fn(&mut Self),
+    // App lifecycle delegates, taken out while they're called
+    init: Option<Hook<S>>,
+    run: Option<Run<S>>,
+    end: Option<Hook<S>>,
+    resized: Option<ResizeHook<S>>,
+    // Bind action handlers by action type
+    handlers: HashMap<TypeId, Handler<S>>,
     // Caches
     caches: HashMap<TypeId, Box<dyn Any>>,
 }
 
 impl App {
     pub fn new() -> App {
+        App::with_state(())
+    }
+
+    // App drawing to an in-memory grid instead of the terminal, for tests and CI.
+    // The terminal is left untouched, inputs have to be passed to step.
+    pub fn headless(width: usize, height: usize) -> App {
+        App::headless_with_state(width, height, ())
+    }
+}
+
+impl<S> App<S> {
+    pub fn with_state(state: S) -> App<S> {
+        App::build(
+            Some(Terminal::initialize()),
+            Box::new(AnsiBackend::new()),
+            state,
+        )
+    }
+
+    pub fn headless_with_state(width: usize, height: usize, state: S) -> App<S> {
+        App::build(None, Box::new(HeadlessBackend::new(width, height)), state)
+    }
+
+    fn build(term: Option<Terminal>, backend: Box<dyn Backend>, state: S) -> App<S> {
         let mut scenes = SceneHandler::new();
         scenes.add(DefaultScene, Frame::new(None));
 
         Self {
-            term: Terminal::initialize(),
+            term,
+            screen: Screen::new(backend),
+            wake: WakePipe::new(),
+            timers: Timers::new(),
+            last_step: Instant::now(),
+            focused: true,
+            clicks: ClickCounter::new(),
+            sequences: SequenceMatcher::new(),
+            drawn_scene: None,
+            last_focus: None,
+            exit_code: None,
+            state,
             scenes,
             opts: AppOptions::new(),
-            init: |_| {},
-            run: |_, _| Some(0),
-            end: |_| {},
+            init: None,
+            run: None,
+            end: None,
+            resized: None,
+            handlers: HashMap::new(),
             caches: HashMap::new(),
         }
     }
 
-    pub fn start(mut self) {
-        (self.init)(&mut self);
+    // Called once before the first frame
+    pub fn on_init(&mut self, init: impl FnMut(&mut App<S>) + 'static) {
+        self.init = Some(Box::new(init));
+    }
+
+    // Called with every input that nothing else handled, and once a frame with None
+    pub fn on_run(&mut self, run: impl FnMut(&mut App<S>, Option<Input>) -> Control + 'static) {
+        self.run = Some(Box::new(run));
+    }
+
+    // Called once the app stopped running
+    pub fn on_end(&mut self, end: impl FnMut(&mut App<S>) + 'static) {
+        self.end = Some(Box::new(end));
+    }
+
+    // Called with the new (columns, rows) once the scenes were laid out again after a resize,
+    // before run gets Input::Resize
+    pub fn on_resize(&mut self, resized: impl FnMut(&mut App<S>, usize, usize) + 'static) {
+        self.resized = Some(Box::new(resized));
+    }
+
+    // Size of the headless backend, the terminal's is looked up when needed
+    fn viewport(&self) -> Option<(usize, usize)> {
+        self.term.is_none().then(|| self.screen.backend().size())
+    }
+
+    // A delegate set again while it ran replaces it
+    pub(crate) fn call_init(&mut self) {
+        let _viewport = Viewport::set(self.viewport());
+
+        if let Some(mut init) = self.init.take() {
+            init(self);
+            self.init.get_or_insert(init);
+        }
+    }
+
+    pub(crate) fn call_end(&mut self) {
+        let _viewport = Viewport::set(self.viewport());
+
+        if let Some(mut end) = self.end.take() {
+            end(self);
+            self.end.get_or_insert(end);
+        }
+    }
+
+    fn call_run(&mut self, input: Option<Input>) -> Control {
+        let Some(mut run) = self.run.take() else {
+            return Control::Continue;
+        };
+
+        let control = run(self, input);
+        self.run.get_or_insert(run);
+        control
+    }
+
+    // Code of the Control::Quit that ended the app
+    pub fn exit_code(&self) -> Option<usize> {
+        self.exit_code
+    }
 
-        if self.opts.no_interrupt {
-            unsafe {
-                signal(SIGINT, handle_sigint as sighandler_t);
+    // Returns whether the app keeps running
+    fn apply(&mut self, control: Control) -> bool {
+        match control {
+            Control::Continue => true,
+            Control::Quit(code) => {
+                self.exit_code = Some(code);
+                false
+            }
+            Control::Switch(key) => {
+                self.scenes.set_dyn(key.as_ref());
+                true
             }
         }
+    }
+
+    fn interrupt_mode(&self) -> Interrupt {
+        if self.opts.interrupt_input {
+            Interrupt::Input
+        } else if self.opts.no_interrupt {
+            Interrupt::Ignore
+        } else {
+            Interrupt::Exit
+        }
+    }
 
-        self.opts.key_protocol.activate();
-        // crate::printf!("\x1b[={};1u", 0b1111);
+    // Runs until something returns Control::Quit, returning its code
+    pub fn start(mut self) -> usize {
+        self.call_init();
 
-        // temp
-        // send mouse events: press, release, move with button, scroll
-        crate::printf!("\x1b[?1002h");
-        // mouse reporting format
-        crate::printf!("\x1b[?1006h");
+        if let Some(term) = &mut self.term {
+            term.set_protocol(self.opts.key_protocol);
+            term.set_mouse_capture(self.opts.capture_mouse);
+        }
 
-        cursor::hide();
+        signal::install(&self.wake.waker(), self.interrupt_mode());
 
-        let mut dim: (usize, usize) = (0, 0);
+        cursor::hide();
 
         loop {
-            let time = Instant::now();
+            if signal::take_suspend() && !self.suspend() {
+                break;
+            }
 
-            if dim != termsz() {
-                dim = termsz();
+            let mut inputs = vec![];
+
+            if signal::take_continue() {
+                self.resume();
+                inputs.push(Input::Resume);
             }
 
-            if !self.run_until_i_can_code() {
-                break;
+            if let Some(term) = &mut self.term {
+                inputs.extend(term.poll());
+            }
+
+            if signal::take_interrupt() {
+                inputs.push(Input::Interrupt);
+            }
+
+            if signal::take_resize() {
+                let (w, h) = termsz();
+                inputs.insert(0, Input::Resize(w, h));
             }
 
-            // if let Some(n) = (self.run)(&mut self, terminal::poll_input()) {
-            //     if n != 0 {
-            //         break;
-            //     }
-            // } else {
-            //     (self.end)(&mut self);
-            //
-            //     drop(self.term);
-            //
-            //     crate::printf!(
-            //         "\n{}App terminated without exit code\n",
-            //         crate::style::color::Color::Red.print()
-            //     );
-            //
-            //     style::reset();
-            //
-            //     return;
-            // }
-
-            set_style(self.current_frame().attr.fill);
-
-            terminal::clear();
-            cursor::home();
-
-            self.current_frame().render();
-
-            style::reset();
-
-            let target_time =
-                std::time::Duration::from_millis(1000 / self.opts.refresh_rate as u64);
-
-            if time.elapsed() < target_time {
-                std::thread::sleep(target_time - time.elapsed());
+            if !self.step(inputs) {
+                break;
             }
+
+            // Sleeps until there's input, a waker is used or something is due
+            let timeout = self
+                .next_deadline()
+                .map(|d| d.saturating_duration_since(Instant::now()));
+
+            self.wake.wait(self.term.is_some(), timeout);
         }
 
-        (self.end)(&mut self);
+        signal::uninstall();
+
+        self.call_end();
+        self.exit_code.unwrap_or(0)
     }
 
-    pub fn run_until_i_can_code(&mut self) -> bool {
-        let inputs = terminal::poll_until_i_can_code();
+    // Run a single frame: every input goes to run, then due timers fire, followed by one call
+    // with None, then the current scene is drawn. Returns false once something quit.
+    pub fn step(&mut self, inputs: Vec<Input>) -> bool {
+        let _viewport = Viewport::set(self.viewport());
+        self.last_step = Instant::now();
+
+        if self.exit_code.is_some() || !self.sync_focus() {
+            return false;
+        }
 
         for input in inputs {
-            if let Some(n) = (self.run)(self, Some(input)) {
-                if n != 0 {
-                    return false;
-                }
-            } else {
-                // (self.end)(&mut self);
+            if !self.handle(input) || !self.sync_focus() {
+                return false;
+            }
+        }
 
-                // drop(self.term);
+        // A sequence nothing followed in time
+        let timeout = self.sequence_timeout();
+        let events =
+            self.match_sequences(|m, candidates| m.expire(candidates, Instant::now(), timeout));
 
-                // crate::printf!(
-                //     "\n{}App terminated without exit code\n",
-                //     crate::style::color::Color::Red.print()
-                // );
+        for event in events {
+            if !self.dispatch_sequence_event(event) {
+                return false;
+            }
+        }
 
-                // style::reset();
+        for callback in self.timers.take_due(Instant::now()) {
+            let control = (callback.borrow_mut())(self);
 
+            if !self.apply(control) || !self.sync_focus() {
                 return false;
             }
         }
 
-        if let Some(n) = (self.run)(self, None) {
-            return n == 0;
+        if !self.dispatch(None) || !self.sync_focus() {
+            return false;
+        }
+
+        let scene = self.scenes.current_pos;
+
+        if self.drawn_scene.replace(scene) != Some(scene) {
+            self.current_frame().attr.mark(Dirty::Paint);
         }
-        // else {
-        // (self.end)(&mut self);
 
-        // drop(self.term);
+        // Frames where nothing changed aren't drawn at all
+        self.screen.render(&mut self.scenes.current().frame);
+
+        true
+    }
+
+    // Hands the terminal back and stops like Ctrl + Z does without the app, after run got
+    // Input::Suspend. Returns false if run quit instead. Headless apps don't stop.
+    pub(crate) fn suspend(&mut self) -> bool {
+        let _viewport = Viewport::set(self.viewport());
+
+        if !self.dispatch(Some(Input::Suspend)) {
+            return false;
+        }
 
-        // crate::printf!(
-        //     "\n{}App terminated without exit code\n",
-        //     crate::style::color::Color::Red.print()
-        // );
+        if let Some(term) = &mut self.term {
+            term.suspend();
 
-        // style::reset();
-        // }
+            // Continuing sets the flag that brings the app back
+            signal::stop();
+        }
 
         true
     }
 
+    // What SIGINT would have done, for Ctrl + C sent as a key
+    fn interrupt(&mut self) -> bool {
+        match self.interrupt_mode() {
+            Interrupt::Ignore => true,
+            Interrupt::Input => self.dispatch(Some(Input::Interrupt)),
+            Interrupt::Exit => signal::exit_interrupted(),
+        }
+    }
+
+    // Takes the terminal back after being stopped, whether it was by suspend or not
+    pub(crate) fn resume(&mut self) {
+        if let Some(term) = &mut self.term {
+            term.resume();
+            cursor::hide();
+        }
+
+        self.screen.invalidate();
+    }
+
+    // Runs a program in the terminal, like an editor, a pager or a shell, with the terminal as
+    // it was before the app. Waits for it to exit, then takes the terminal back and draws the
+    // screen again on the next frame. Ctrl + Z in the program stops the app as well, fg brings
+    // back both.
+    pub fn run_external(&mut self, command: &mut Command) -> io::Result<ExitStatus> {
+        if let Some(term) = &mut self.term {
+            term.suspend();
+        }
+
+        let status = signal::in_background(|| signal::wait_foreground(command.spawn()?));
+
+        self.resume();
+        status
+    }
+
+    // Lays every scene out again for the new size, percentages included
+    fn resize(&mut self, width: usize, height: usize) {
+        for scene in self.scenes.scenes.iter_mut() {
+            scene.frame.relayout();
+        }
+
+        self.screen.invalidate();
+        if let Some(mut resized) = self.resized.take() {
+            resized(self, width, height);
+            self.resized.get_or_insert(resized);
+        }
+    }
+
+    // Earliest time the app has to step again without new input: a timer, a key sequence or
+    // escape sequence giving up, or the next frame when stepping at a fixed rate
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        let frame = self.opts.fixed_rate.then(|| {
+            self.last_step + Duration::from_secs(1) / self.opts.refresh_rate.max(1) as u32
+        });
+
+        [
+            self.timers.next(),
+            self.sequences.deadline(self.sequence_timeout()),
+            self.term.as_ref().and_then(|t| t.deadline()),
+            frame,
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    // Call back once after the delay, on the first step after it passed. Callbacks return a
+    // Control like run.
+    pub fn after(
+        &mut self,
+        delay: Duration,
+        callback: impl FnMut(&mut App<S>) -> Control + 'static,
+    ) -> TimerId {
+        let callback: TimerCallback<S> = Rc::new(RefCell::new(callback));
+        self.timers.add(Instant::now() + delay, None, callback)
+    }
+
+    // Call back every interval until cancelled
+    pub fn every(
+        &mut self,
+        interval: Duration,
+        callback: impl FnMut(&mut App<S>) -> Control + 'static,
+    ) -> TimerId {
+        let callback: TimerCallback<S> = Rc::new(RefCell::new(callback));
+        self.timers
+            .add(Instant::now() + interval, Some(interval), callback)
+    }
+
+    // Returns whether the timer hadn't fired for good yet
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        self.timers.cancel(id)
+    }
+
+    // Handle for stepping the app from another thread, like when a background job finished
+    pub fn waker(&self) -> Waker {
+        self.wake.waker()
+    }
+
+    fn handle(&mut self, input: Input) -> bool {
+        let mut multi_click = None;
+
+        // The kitty protocol sends Ctrl + C and Ctrl + Z as keys instead of raising the signals
+        if self.opts.key_protocol == Protocol::Kitty
+            && let Input::Key(key @ (Key::c | Key::z), KeyState::Press, ref mods) = input
+            && *mods == Modifier::Ctrl
+        {
+            return match key {
+                Key::c => self.interrupt(),
+                _ => self.suspend(),
+            };
+        }
+
+        match input {
+            Input::Resize(w, h) => self.resize(w, h),
+            Input::FocusGained => self.focused = true,
+            Input::FocusLost => self.focused = false,
+            Input::Mouse(btn, MouseState::Click, ref mods, pos) => {
+                let interval = Duration::from_millis(self.opts.click_interval as u64);
+
+                multi_click = match self.clicks.click(btn, pos, Instant::now(), interval) {
+                    2 => Some(Input::Mouse(
+                        btn,
+                        MouseState::DoubleClick,
+                        mods.clone(),
+                        pos,
+                    )),
+                    3 => Some(Input::Mouse(
+                        btn,
+                        MouseState::TripleClick,
+                        mods.clone(),
+                        pos,
+                    )),
+                    _ => None,
+                };
+            }
+            _ => {}
+        }
+
+        // Key presses can be part of a sequence, everything else can only be bound alone. What
+        // isn't a key gives out the pending inputs first, so everything reaches run in order.
+        let events = match input {
+            Input::Key(_, KeyState::Press, _) => {
+                let timeout = self.sequence_timeout();
+                self.match_sequences(|m, candidates| {
+                    m.feed(input, candidates, Instant::now(), timeout)
+                })
+            }
+            Input::Key(..) => self.sequences.hold(input),
+            _ => {
+                let mut events = self.match_sequences(|m, candidates| m.flush(candidates));
+                events.push(SequenceEvent::Unmatched(input));
+                events
+            }
+        };
+
+        let running = events
+            .into_iter()
+            .all(|event| self.dispatch_sequence_event(event));
+
+        running && multi_click.is_none_or(|input| self.dispatch(Some(input)))
+    }
+
+    fn dispatch_sequence_event(&mut self, event: SequenceEvent) -> bool {
+        match event {
+            SequenceEvent::Matched(sequence) => match self.bound_action(sequence.inputs()) {
+                Some((handler, action)) => {
+                    let control = (handler.borrow_mut())(self, action.as_ref());
+                    self.apply(control)
+                }
+                None => true,
+            },
+            SequenceEvent::Unmatched(input) => self.dispatch(Some(input)),
+        }
+    }
+
+    fn dispatch(&mut self, input: Option<Input>) -> bool {
+        // Inputs bound to a handled action, used to move focus or handled by a widget don't
+        // reach run
+        let control = if let Some(input) = &input
+            && let Some((handler, action)) = self.bound_action(std::slice::from_ref(input))
+        {
+            (handler.borrow_mut())(self, action.as_ref())
+        } else if let Some(input) = &input
+            && (self.navigate(input) || self.route(input))
+        {
+            Control::Continue
+        } else {
+            self.call_run(input)
+        };
+
+        self.apply(control)
+    }
+
+    // Moves focus for Tab and the arrow keys if enabled. Returns whether it moved.
+    fn navigate(&mut self, input: &Input) -> bool {
+        let frame = &mut self.scenes.current().frame;
+
+        let Input::Key(key, KeyState::Press, mods) = input else {
+            return false;
+        };
+
+        match key {
+            Key::Tab if self.opts.tab_focus && mods.is_empty() => frame.focus_next(),
+            Key::Tab if self.opts.tab_focus && *mods == Modifier::Shift => frame.focus_prev(),
+            Key::Up | Key::Down | Key::Left | Key::Right
+                if self.opts.arrow_focus && mods.is_empty() =>
+            {
+                frame.focus_toward(match key {
+                    Key::Up => Direction::Up,
+                    Key::Down => Direction::Down,
+                    Key::Left => Direction::Left,
+                    _ => Direction::Right,
+                })
+            }
+            _ => false,
+        }
+    }
+
+    // Mouse inputs go to the widget under the pointer, clicks also focus it if they can.
+    // Returns whether a widget handled it.
+    fn route(&mut self, input: &Input) -> bool {
+        let frame = &mut self.scenes.current().frame;
+
+        if let Input::Mouse(_, MouseState::Click, _, pos) = input {
+            frame.focus_at(*pos);
+        }
+
+        frame.route(input)
+    }
+
+    // Keeps a widget focused in the current scene and tells run when focus moved
+    fn sync_focus(&mut self) -> bool {
+        let scene = self.scenes.current_pos;
+        let frame = &mut self.scenes.current().frame;
+
+        frame.ensure_focus();
+
+        let focus = frame.focused_path().map(|path| {
+            let tag = frame
+                .widget_at_path(&path)
+                .map(|w| w.style().tag.clone())
+                .filter(|t| !t.is_empty());
+
+            (scene, path, tag)
+        });
+
+        let moved = match (&self.last_focus, &focus) {
+            (Some((s1, p1, _)), Some((s2, p2, _))) => s1 != s2 || p1 != p2,
+            (None, None) => false,
+            _ => true,
+        };
+
+        if !moved {
+            return true;
+        }
+
+        let from = self.last_focus.take().and_then(|(_, _, tag)| tag);
+        let to = focus.as_ref().and_then(|(_, _, tag)| tag.clone());
+        self.last_focus = focus;
+
+        self.dispatch(Some(Input::FocusChanged(from, to)))
+    }
+
+    // Runs f on the sequence matcher, with the sequences of binds that have a handler as
+    // candidates
+    fn match_sequences<T>(&mut self, f: impl FnOnce(&mut SequenceMatcher, &[&Sequence]) -> T) -> T {
+        let handlers = &self.handlers;
+        let candidates: Vec<&Sequence> = self
+            .scenes
+            .current()
+            .frame
+            .binds()
+            .into_iter()
+            .filter(|b| handled(b, handlers))
+            .map(|b| &b.sequence)
+            .collect();
+
+        f(&mut self.sequences, &candidates)
+    }
+
+    // Handler and action of the first bind with a handler for inputs. Only that action is
+    // cloned.
+    fn bound_action(&mut self, inputs: &[Input]) -> Option<(Handler<S>, Box<dyn Action>)> {
+        let handlers = &self.handlers;
+        let binds = self.scenes.current().frame.binds();
+        let bind = binds
+            .into_iter()
+            .find(|b| handled(b, handlers) && b.sequence.matches(inputs))?;
+        let action = bind.action.clone()?;
+        let handler = handlers.get(&Any::type_id(action.as_any()))?.clone();
+
+        Some((handler, action))
+    }
+
+    fn sequence_timeout(&self) -> Duration {
+        Duration::from_millis(self.opts.sequence_timeout as u64)
+    }
+
+    // Inputs typed so far of a sequence that hasn't completed yet
+    pub fn pending_sequence(&self) -> &[Input] {
+        self.sequences.pending()
+    }
+
+    // Binds that the pending inputs could still become, for showing hints
+    pub fn pending_binds(&mut self) -> Vec<Bind> {
+        let pending = self.sequences.pending();
+
+        if pending.is_empty() {
+            return vec![];
+        }
+
+        self.scenes
+            .current()
+            .frame
+            .binds()
+            .into_iter()
+            .filter(|b| b.sequence.extends(pending))
+            .cloned()
+            .collect()
+    }
+
+    // Handle every bound action of type A, returning a Control like run
+    pub fn on<A: Action>(&mut self, mut handler: impl FnMut(&mut App<S>, &A) -> Control + 'static) {
+        let handler: Handler<S> = Rc::new(RefCell::new(
+            move |app: &mut App<S>, action: &dyn Action| match action.as_any().downcast_ref::<A>() {
+                Some(action) => handler(app, action),
+                None => Control::Continue,
+            },
+        ));
+
+        self.handlers.insert(TypeId::of::<A>(), handler);
+    }
+
+    // Whether the terminal window has focus
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    // Tag of the focused widget in the current scene
+    pub fn focused_tag(&mut self) -> Option<String> {
+        self.current_frame()
+            .focused()
+            .map(|w| w.style().tag.clone())
+            .filter(|t| !t.is_empty())
+    }
+
+    // Focus the focusable widget with the tag in the current scene, run gets FocusChanged once
+    // the current input is handled. Returns whether there was such a widget.
+    pub fn focus(&mut self, tag: &str) -> bool {
+        self.current_frame().focus(tag)
+    }
+
+    // Widget under a point of the last frame drawn
+    pub fn widget_at(&mut self, pos: impl Into<Point>) -> Option<&mut Box<dyn Widget>> {
+        self.current_frame().widget_at(pos.into())
+    }
+
+    pub fn tag_at(&mut self, pos: impl Into<Point>) -> Option<String> {
+        self.widget_at(pos)
+            .map(|w| w.style().tag.clone())
+            .filter(|t| !t.is_empty())
+    }
+
+    // Last frame drawn by step
+    pub fn screen(&self) -> &Buffer {
+        self.screen.last_frame()
+    }
+
+    pub(crate) fn backend_mut(&mut self) -> &mut dyn Backend {
+        self.screen.backend_mut().as_mut()
+    }
+
     pub fn cache<T: 'static>(&mut self) -> &mut Cache<T> {
         let id = TypeId::of::<T>();
 
@@ -189,12 +773,12 @@ impl App {
         self.scenes.current().key.as_any().downcast_ref::<T>()
     }
 
+    // The widget is drawn again on the next frame, since it's likely being changed
     pub fn get_widget<T: Widget>(&mut self, tag: &str) -> Option<&mut T> {
-        // self.has_changed = true;
-
         if let Some(widget) = self.current_frame().get_child(tag)
             && let Some(widget_as) = widget.as_any_mut().downcast_mut::<T>()
         {
+            widget_as.style_mut().mark(Dirty::Paint);
             Some(widget_as)
         } else {
             None
@@ -202,8 +786,6 @@ impl App {
     }
 
     pub fn hide_widget(&mut self, tag: &str) {
-        // self.has_changed = true;
-
         let (_, children) = self.current_frame().split_mut();
 
         for child in children.iter_mut() {
@@ -219,8 +801,6 @@ impl App {
     }
 
     pub fn show_widget(&mut self, tag: &str) {
-        // self.has_changed = true;
-
         let (_, children) = self.current_frame().split_mut();
 
         for child in children.iter_mut() {
@@ -236,8 +816,6 @@ impl App {
     }
 
     pub fn toggle_visiblity_of(&mut self, tag: &str) {
-        // self.has_changed = true;
-
         let (_, children) = self.current_frame().split_mut();
 
         for child in children.iter_mut() {
@@ -253,14 +831,12 @@ impl App {
     }
 
     pub fn map_all(&mut self, map: fn(&mut Box<dyn Widget>)) {
-        // self.has_changed = true;
-
         self.current_frame().map_all(map);
+        self.current_frame().attr.mark(Dirty::Paint);
     }
 
     pub fn style_all(&mut self, map: fn(&mut Attr)) {
-        // self.has_changed = true;
-
         self.current_frame().style_all(map);
+        self.current_frame().attr.mark(Dirty::Paint);
     }
 }
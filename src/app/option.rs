@@ -1,13 +1,31 @@
-use crate::{input::key::Protocol, theme::Theme};
+use crate::{
+    input::{key::Protocol, mouse::MouseCapture},
+    theme::Theme,
+};
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum AppOption {
     Theme(Theme),
+    // Ctrl + C doesn't end the app
     NoInterrupt(bool),
+    // Ctrl + C reaches run as Input::Interrupt, whatever NoInterrupt is set to
+    InterruptInput(bool),
+    // Frames per second when stepping at a fixed rate
     RefreshRate(usize),
-    CaptureMouse(bool),
+    // Step at the refresh rate even when nothing happened, for animations. Otherwise the app
+    // only steps for inputs, timers and wakers.
+    FixedRate(bool),
+    CaptureMouse(MouseCapture),
+    // Longest time between clicks that still counts as a double or triple click, in ms
+    ClickInterval(usize),
+    // How long a key sequence waits for its next key, in ms
+    SequenceTimeout(usize),
     KeyProtocol(Protocol),
+    // Tab and Shift + Tab move focus between focusable widgets
+    TabFocus(bool),
+    // Arrow keys move focus to the closest focusable widget in their direction
+    ArrowFocus(bool),
 }
 
 // TODO
@@ -18,9 +36,15 @@ pub enum AppOption {
 pub struct AppOptions {
     pub(crate) theme: Theme,
     pub(crate) no_interrupt: bool,
+    pub(crate) interrupt_input: bool,
     pub(crate) refresh_rate: usize,
-    pub(crate) capture_mouse: bool,
+    pub(crate) fixed_rate: bool,
+    pub(crate) capture_mouse: MouseCapture,
+    pub(crate) click_interval: usize,
+    pub(crate) sequence_timeout: usize,
     pub(crate) key_protocol: Protocol,
+    pub(crate) tab_focus: bool,
+    pub(crate) arrow_focus: bool,
 }
 
 impl AppOptions {
@@ -28,9 +52,15 @@ impl AppOptions {
         Self {
             theme: Theme::new(),
             no_interrupt: true,
+            interrupt_input: false,
             refresh_rate: 30,
-            capture_mouse: false,
+            fixed_rate: false,
+            capture_mouse: MouseCapture::Drag,
+            click_interval: 400,
+            sequence_timeout: 1000,
             key_protocol: Protocol::Default,
+            tab_focus: true,
+            arrow_focus: false,
         }
     }
 
@@ -38,9 +68,15 @@ impl AppOptions {
         match option {
             AppOption::Theme(t) => self.theme = t,
             AppOption::NoInterrupt(b) => self.no_interrupt = b,
+            AppOption::InterruptInput(b) => self.interrupt_input = b,
             AppOption::RefreshRate(n) => self.refresh_rate = n,
-            AppOption::CaptureMouse(b) => self.capture_mouse = b,
+            AppOption::FixedRate(b) => self.fixed_rate = b,
+            AppOption::CaptureMouse(c) => self.capture_mouse = c,
+            AppOption::ClickInterval(n) => self.click_interval = n,
+            AppOption::SequenceTimeout(n) => self.sequence_timeout = n,
             AppOption::KeyProtocol(p) => self.key_protocol = p,
+            AppOption::TabFocus(b) => self.tab_focus = b,
+            AppOption::ArrowFocus(b) => self.arrow_focus = b,
         }
     }
 }
@@ -0,0 +1,285 @@
+use super::{App, control::Control, timer::TimerId};
+use crate::{
+    input::{Input, binds::Action},
+    panel::frame::Frame,
+};
+use std::time::Duration;
+
+// An app whose state only changes through messages, with the current scene's frame built from
+// it again after every change. Binds in the view with a message as their action send it to
+// update. Widgets keep their focus and are only drawn again when they changed, tagged ones
+// even when they moved.
+pub trait Program: Sized + 'static {
+    type Msg: Action + Clone;
+
+    fn init(&mut self) -> Command<Self::Msg> {
+        Command::None
+    }
+
+    fn update(&mut self, msg: Self::Msg) -> Command<Self::Msg>;
+
+    fn view(&self) -> Frame;
+
+    // Message for an input no bind took, like text typed into a field or a resize
+    fn input(&self, _input: &Input) -> Option<Self::Msg> {
+        None
+    }
+}
+
+// What the app does for the program after an update
+pub enum Command<M> {
+    None,
+    Batch(Vec<Command<M>>),
+    // Updated with right away, before the view is built
+    Msg(M),
+    After(Duration, M),
+    // Sent until cancelled with the key. Another one with the same key replaces it.
+    Every(&'static str, Duration, M),
+    Cancel(&'static str),
+    Focus(String),
+    Quit(usize),
+}
+
+// Timer of a Command::Every, kept in the app's cache under its key
+struct Ticker(TimerId);
+
+impl<P: Program> App<P> {
+    // Runs the state as a program, replacing init, run and whatever handled its messages
+    pub fn mount(&mut self) {
+        self.on_init(|app| {
+            let command = app.state.init();
+            let control = app.perform(command);
+            app.build_view();
+            app.apply(control);
+        });
+
+        self.on_run(|app, input| match input.and_then(|i| app.state.input(&i)) {
+            Some(msg) => app.send(msg),
+            None => Control::Continue,
+        });
+
+        self.on::<P::Msg>(|app, msg| app.send(msg.clone()));
+    }
+
+    // Updates the program with msg, then builds the view again
+    pub fn send(&mut self, msg: P::Msg) -> Control {
+        let command = self.state.update(msg);
+        let control = self.perform(command);
+
+        self.build_view();
+        control
+    }
+
+    fn perform(&mut self, command: Command<P::Msg>) -> Control {
+        match command {
+            Command::None => Control::Continue,
+            Command::Batch(commands) => {
+                for command in commands {
+                    let control = self.perform(command);
+
+                    if control != Control::Continue {
+                        return control;
+                    }
+                }
+
+                Control::Continue
+            }
+            Command::Msg(msg) => {
+                let command = self.state.update(msg);
+                self.perform(command)
+            }
+            Command::After(delay, msg) => {
+                self.after(delay, move |app| app.send(msg.clone()));
+                Control::Continue
+            }
+            Command::Every(key, interval, msg) => {
+                self.perform(Command::Cancel(key));
+
+                let id = self.every(interval, move |app| app.send(msg.clone()));
+                self.cache::<Ticker>().add(key, Ticker(id));
+                Control::Continue
+            }
+            Command::Cancel(key) => {
+                if let Some(ticker) = self.cache::<Ticker>().remove(key) {
+                    self.cancel(ticker.0);
+                }
+
+                Control::Continue
+            }
+            Command::Focus(tag) => {
+                self.focus(&tag);
+                Control::Continue
+            }
+            Command::Quit(code) => Control::Quit(code),
+        }
+    }
+
+    fn build_view(&mut self) {
+        let view = self.state.view();
+        self.current_frame().reconcile(view);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::{binds::Binds, key::Key},
+        make_action,
+        panel::Panel,
+        testing::driver::AppDriver,
+        widget::{attr::Attr, label::Label},
+    };
+
+    #[derive(Clone, PartialEq, Debug)]
+    enum Msg {
+        Add(usize),
+        Typed(char),
+        Done,
+        Tick,
+        Start,
+        Stop,
+        Quit,
+    }
+
+    make_action!(Msg);
+
+    #[derive(Default)]
+    struct Counter {
+        count: usize,
+        typed: String,
+        done: bool,
+        ticks: usize,
+    }
+
+    impl Program for Counter {
+        type Msg = Msg;
+
+        fn init(&mut self) -> Command<Msg> {
+            Command::Batch(vec![
+                Command::Msg(Msg::Add(1)),
+                Command::After(Duration::ZERO, Msg::Done),
+            ])
+        }
+
+        fn update(&mut self, msg: Msg) -> Command<Msg> {
+            match msg {
+                Msg::Add(n) => self.count += n,
+                Msg::Typed(c) => self.typed.push(c),
+                Msg::Done => self.done = true,
+                Msg::Tick => self.ticks += 1,
+                Msg::Start => return Command::Every("tick", Duration::ZERO, Msg::Tick),
+                Msg::Stop => return Command::Cancel("tick"),
+                Msg::Quit => return Command::Quit(self.count),
+            }
+
+            Command::None
+        }
+
+        fn view(&self) -> Frame {
+            let mut frame = Frame::new(
+                Attr::new()
+                    .binds(
+                        Binds::new()
+                            .action(Key::Plus.into(), "add", Msg::Add(1))
+                            .action(Key::q.into(), "quit", Msg::Quit),
+                    )
+                    .wrap(),
+            );
+
+            frame.addm(vec![
+                Label::new(
+                    self.count.to_string(),
+                    Attr::new().tag("count").size(6usize, 3usize).wrap(),
+                ),
+                Label::new(
+                    self.typed.clone(),
+                    Attr::new()
+                        .tag("typed")
+                        .size(6usize, 3usize)
+                        .focusable()
+                        .wrap(),
+                ),
+            ]);
+
+            if self.done {
+                frame.add(Label::new("done", Attr::new().size(6usize, 3usize).wrap()));
+            }
+
+            frame
+        }
+
+        fn input(&self, input: &Input) -> Option<Msg> {
+            match input {
+                Input::Key(key, _, _) => key.to_char().map(Msg::Typed),
+                _ => None,
+            }
+        }
+    }
+
+    fn driver() -> AppDriver<Counter> {
+        let mut app = App::headless_with_state(20, 16, Counter::default());
+        app.mount();
+        AppDriver::with(app)
+    }
+
+    fn text(driver: &mut AppDriver<Counter>, tag: &str) -> String {
+        driver.widget::<Label>(tag).unwrap().text.clone()
+    }
+
+    #[test]
+    fn binds_send_messages() {
+        let mut driver = driver();
+        driver.step();
+
+        // Init added one, its timer fired on the first step
+        assert_eq!(text(&mut driver, "count"), "1");
+        assert!(driver.app().state.done);
+        assert_eq!(driver.app().current_frame().split().1.len(), 3);
+
+        driver.key(Key::Plus).key(Key::Plus).step();
+        assert_eq!(text(&mut driver, "count"), "3");
+        assert!(driver.screen().lines()[3].contains('3'));
+    }
+
+    #[test]
+    fn other_inputs_map_to_messages() {
+        let mut driver = driver();
+        driver.type_str("ab").step();
+
+        assert_eq!(driver.app().state.typed, "ab");
+        assert_eq!(text(&mut driver, "typed"), "ab");
+        assert_eq!(driver.app().focused_tag().as_deref(), Some("typed"));
+    }
+
+    #[test]
+    fn quit_with_code() {
+        let mut driver = driver();
+        driver.key(Key::Plus).key(Key::q);
+
+        assert!(!driver.step());
+        assert_eq!(driver.app().exit_code(), Some(2));
+    }
+
+    #[test]
+    fn cancel_repeating_message() {
+        let mut driver = driver();
+        driver.step();
+
+        let ticks = |driver: &mut AppDriver<Counter>| driver.app().state.ticks;
+
+        driver.app().send(Msg::Start);
+        driver.step_n(2);
+        assert_eq!(ticks(&mut driver), 2);
+
+        // Starting again replaces the timer instead of adding another
+        driver.app().send(Msg::Start);
+        driver.step();
+        assert_eq!(ticks(&mut driver), 3);
+
+        driver.app().send(Msg::Stop);
+        driver.step_n(2);
+        assert_eq!(ticks(&mut driver), 3);
+        assert_eq!(driver.app().next_deadline(), None);
+    }
+}
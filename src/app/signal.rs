@@ -0,0 +1,236 @@
+use super::{terminal, wake::Waker};
+use libc::{
+    CLD_STOPPED, P_PID, SIG_DFL, SIGCONT, SIGHUP, SIGINT, SIGTERM, SIGTSTP, SIGWINCH, WEXITED,
+    WNOWAIT, WSTOPPED, c_int, c_void, id_t, pid_t, sighandler_t, siginfo_t, signal,
+};
+use std::{
+    io,
+    process::{Child, ExitStatus},
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
+};
+
+// Signal handlers only set a flag and wake the main loop, which picks the flags up between
+// steps. Anything more isn't safe to do inside a handler.
+
+static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+static RESIZED: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+static CONTINUED: AtomicBool = AtomicBool::new(false);
+
+// What Ctrl + C does while the app runs
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Interrupt {
+    Ignore,
+    // Reaches the app as Input::Interrupt
+    Input,
+    // Restores the terminal and ends the process
+    Exit,
+}
+
+fn wake() {
+    let fd = WAKE_FD.load(Ordering::Relaxed);
+
+    if fd >= 0 {
+        unsafe {
+            libc::write(fd, [1u8].as_ptr() as *const c_void, 1);
+        }
+    }
+}
+
+fn set_handler(sig: c_int, handler: extern "C" fn(c_int)) {
+    unsafe {
+        signal(sig, handler as *const () as sighandler_t);
+    }
+}
+
+extern "C" fn handle_sigwinch(_: c_int) {
+    RESIZED.store(true, Ordering::Relaxed);
+    wake();
+}
+
+// Not SIG_IGN, that would be passed on to programs the app runs
+extern "C" fn ignore(_: c_int) {}
+
+extern "C" fn handle_sigint(_: c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    wake();
+}
+
+extern "C" fn handle_sigtstp(_: c_int) {
+    SUSPENDED.store(true, Ordering::Relaxed);
+    wake();
+}
+
+extern "C" fn handle_sigcont(_: c_int) {
+    CONTINUED.store(true, Ordering::Relaxed);
+    wake();
+}
+
+// The terminal is put back first, then the signal does what it would have done
+extern "C" fn handle_exit(sig: c_int) {
+    terminal::restore();
+
+    unsafe {
+        signal(sig, SIG_DFL);
+        libc::raise(sig);
+    }
+}
+
+// Restores the terminal and ends the process like SIGINT does by default, for Ctrl + C sent as
+// a key
+pub(crate) fn exit_interrupted() -> ! {
+    handle_exit(SIGINT);
+    unreachable!("SIGINT didn't end the process")
+}
+
+pub(crate) fn install(waker: &Waker, interrupt: Interrupt) {
+    WAKE_FD.store(waker.as_raw_fd(), Ordering::Relaxed);
+
+    set_handler(SIGWINCH, handle_sigwinch);
+    set_handler(SIGTERM, handle_exit);
+    set_handler(SIGHUP, handle_exit);
+    set_handler(SIGTSTP, handle_sigtstp);
+    set_handler(SIGCONT, handle_sigcont);
+
+    match interrupt {
+        Interrupt::Ignore => set_handler(SIGINT, ignore),
+        Interrupt::Input => set_handler(SIGINT, handle_sigint),
+        Interrupt::Exit => set_handler(SIGINT, handle_exit),
+    }
+}
+
+// Before the wake pipe is closed. SIGINT, SIGTERM, SIGHUP and SIGTSTP keep restoring the
+// terminal until the app is dropped, after that they do what they would have anyway.
+pub(crate) fn uninstall() {
+    set_handler(SIGINT, handle_exit);
+    set_handler(SIGTSTP, handle_exit);
+
+    unsafe {
+        signal(SIGWINCH, SIG_DFL);
+        signal(SIGCONT, SIG_DFL);
+    }
+
+    WAKE_FD.store(-1, Ordering::Relaxed);
+}
+
+// Whether the terminal was resized since the last call
+pub(crate) fn take_resize() -> bool {
+    RESIZED.swap(false, Ordering::Relaxed)
+}
+
+// Whether Ctrl + C was pressed since the last call
+pub(crate) fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::Relaxed)
+}
+
+// Whether Ctrl + Z was pressed since the last call
+pub(crate) fn take_suspend() -> bool {
+    SUSPENDED.swap(false, Ordering::Relaxed)
+}
+
+// Whether the process was continued after being stopped since the last call
+pub(crate) fn take_continue() -> bool {
+    CONTINUED.swap(false, Ordering::Relaxed)
+}
+
+// Stops the process the way Ctrl + Z does by default, returning once it's continued
+pub(crate) fn stop() {
+    unsafe {
+        signal(SIGTSTP, SIG_DFL);
+        libc::raise(SIGTSTP);
+    }
+
+    set_handler(SIGTSTP, handle_sigtstp);
+}
+
+// Ctrl + C is for the program in front while f runs, so the app ignores it. Ctrl + Z stops the
+// app along with it like it would without the app, so the shell gets the job back, and fg
+// continues both.
+pub(crate) fn in_background<T>(f: impl FnOnce() -> T) -> T {
+    let previous = [
+        (SIGINT, ignore as *const () as sighandler_t),
+        (SIGTSTP, SIG_DFL),
+    ]
+    .map(|(sig, handler)| unsafe { (sig, signal(sig, handler)) });
+
+    let result = f();
+
+    for (sig, previous) in previous {
+        unsafe {
+            signal(sig, previous);
+        }
+    }
+
+    result
+}
+
+// Waits for child to exit. Some programs stop only themselves on Ctrl + Z, so a stopped child
+// stops the app too, and is continued once the app is.
+pub(crate) fn wait_foreground(mut child: Child) -> io::Result<ExitStatus> {
+    let pid = child.id() as id_t;
+
+    loop {
+        // Left to be reaped by wait once it exited
+        let mut info: siginfo_t = unsafe { std::mem::zeroed() };
+
+        if unsafe { libc::waitid(P_PID, pid, &mut info, WEXITED | WSTOPPED | WNOWAIT) } < 0 {
+            let err = io::Error::last_os_error();
+
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+
+            return Err(err);
+        }
+
+        if info.si_code != CLD_STOPPED {
+            return child.wait();
+        }
+
+        unsafe {
+            libc::raise(SIGTSTP);
+            libc::kill(pid as pid_t, SIGCONT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::wake::WakePipe;
+    use std::{os::unix::process::ExitStatusExt, process::Command, time::Duration};
+
+    #[test]
+    fn flags_wake_the_loop() {
+        let pipe = WakePipe::new();
+        WAKE_FD.store(pipe.waker().as_raw_fd(), Ordering::Relaxed);
+
+        handle_sigint(SIGINT);
+        handle_sigwinch(SIGWINCH);
+        handle_sigtstp(SIGTSTP);
+        handle_sigcont(SIGCONT);
+        WAKE_FD.store(-1, Ordering::Relaxed);
+
+        assert!(pipe.wait(false, Some(Duration::ZERO)));
+        assert!(take_interrupt());
+        assert!(take_resize());
+        assert!(take_suspend());
+        assert!(take_continue());
+        assert!(!take_interrupt());
+        assert!(!take_resize());
+        assert!(!take_suspend());
+        assert!(!take_continue());
+    }
+
+    #[test]
+    fn wait_for_exit() {
+        let wait = |script: &str| {
+            let child = Command::new("sh").args(["-c", script]).spawn().unwrap();
+            in_background(|| wait_foreground(child)).unwrap()
+        };
+
+        assert_eq!(wait("exit 3").code(), Some(3));
+        assert_eq!(wait("kill -TERM $$").signal(), Some(SIGTERM));
+    }
+}
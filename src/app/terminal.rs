@@ -1,13 +1,20 @@
-use crate::input::key::Protocol;
-use crate::input::{Input, parse};
-use crate::{printf, printlnf};
+use crate::input::{Input, ParseError, parse, tokenizer::Tokenizer};
+use crate::input::{key::Protocol, mouse::MouseCapture};
+use crate::printlnf;
 use libc::*;
-use std::io::{Read, stdin};
+use std::cell::Cell;
+use std::io::{Read, Write, stdin, stdout};
 use std::os::unix::io::AsRawFd;
+use std::sync::{
+    Once, OnceLock,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, Instant};
 
-pub(crate) fn clear() {
-    printf!("\x1b[2J");
-}
+// How long an unfinished escape sequence waits for the rest of it before it's taken as is
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(30);
+// Pastes can stall a lot longer over a slow link without being over
+const PASTE_TIMEOUT: Duration = Duration::from_secs(2);
 
 // (x, y)
 pub(crate) fn termsz() -> (usize, usize) {
@@ -25,42 +32,159 @@ pub(crate) fn termsz() -> (usize, usize) {
     (winsz.ws_col as usize, winsz.ws_row as usize)
 }
 
-// Temporary until events are implemented
-pub(crate) fn poll_input() -> Option<Input> {
-    let mut buf = [0; 512];
+// Turns off whatever mouse capture, paste and focus reporting was on and leaves the saved
+// screen, as plain bytes so it can be written from a signal handler
+const RESET: &[u8] =
+    b"\x1b[?1000l\x1b[?1002l\x1b[?1003l\x1b[?1006l\x1b[?2004l\x1b[?1004l\x1b[?47l\x1b[?25h\n";
+
+// Mode from before raw mode was set, kept here so signal handlers and the panic hook can get
+// to it without the Terminal
+static CANONICAL: OnceLock<termios> = OnceLock::new();
+// Whether the terminal is in raw mode, so it's only restored once
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+// Whether kitty key flags were pushed and have to be popped
+static KITTY: AtomicBool = AtomicBool::new(false);
+
+static PANIC_HOOK: Once = Once::new();
+
+thread_local! {
+    // Whether this thread initialized the terminal. Panics elsewhere, like in a worker thread
+    // that gets joined, leave the terminal alone.
+    static OWNER: Cell<bool> = const { Cell::new(false) };
+}
 
-    match stdin().read(&mut buf) {
-        Ok(0) | Err(_) => None,
-        Ok(n) => parse(&buf[..n]),
+// Puts the terminal back the way it was before Terminal::initialize. Only makes calls that are
+// safe inside signal handlers, so it can be used from them.
+pub(crate) fn restore() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
     }
-    // None
+
+    let write = |bytes: &[u8]| unsafe {
+        libc::write(STDOUT_FILENO, bytes.as_ptr() as *const c_void, bytes.len());
+    };
+
+    unsafe {
+        if let Some(mode) = CANONICAL.get() {
+            tcsetattr(STDIN_FILENO, TCSANOW, mode);
+        }
+
+        // Stdin is shared with the shell, which doesn't expect it to be non-blocking
+        fcntl(
+            STDIN_FILENO,
+            F_SETFL,
+            fcntl(STDIN_FILENO, F_GETFL) & !O_NONBLOCK,
+        );
+    }
+
+    if KITTY.swap(false, Ordering::SeqCst) {
+        write(b"\x1b[<u");
+    }
+
+    write(RESET);
 }
 
-pub(crate) fn poll_until_i_can_code() -> Vec<Input> {
-    let mut inputs = vec![];
+// Restores the terminal before the panic message is printed, otherwise it ends up on the
+// saved screen with the shell left in raw mode. Only for panics on the thread running the app.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
 
-    let mut buf = [0; 512];
+        std::panic::set_hook(Box::new(move |info| {
+            on_panic(restore);
+            previous(info);
+        }));
+    });
+}
 
-    if let Ok(n) = stdin().read(&mut buf) {
-        if n > 2 {
-            let input_parts = buf[..n].split_inclusive(|b| *b == b'u');
+// Calls restore if the thread that panicked is the one running the app
+fn on_panic(restore: impl FnOnce()) {
+    if OWNER.with(|o| o.get()) {
+        restore();
+    }
+}
 
-            for part in input_parts {
-                if let Some(input) = parse(part) {
-                    inputs.push(input);
-                }
-            }
-        } else if let Some(input) = parse(&buf[..n]) {
-            inputs.push(input);
+// Raw mode from the saved canonical mode, plus the reporting every app uses
+fn enter_raw() {
+    let Some(mut raw_mode) = CANONICAL.get().copied() else {
+        return;
+    };
+
+    raw_mode.c_lflag &= !(ICANON | ECHO);
+
+    // Save screen
+    printlnf!("\x1b[?47h");
+
+    // Bracketed paste
+    crate::printf!("\x1b[?2004h");
+
+    // Focus in/out reporting
+    crate::printf!("\x1b[?1004h");
+
+    let stdin_fd = stdin().as_raw_fd();
+
+    unsafe {
+        // None-blocking
+        fcntl(
+            stdin_fd,
+            F_SETFL,
+            libc::fcntl(stdin_fd, F_GETFL) | O_NONBLOCK,
+        );
+
+        // Set raw mode
+        tcsetattr(STDIN_FILENO, TCSANOW, &raw_mode);
+    }
+
+    ACTIVE.store(true, Ordering::SeqCst);
+}
+
+// Splits what was read into tokens, keeping an unfinished sequence until the rest of it arrives
+// or nothing did for a while
+struct Reader {
+    tokenizer: Tokenizer,
+    // When the tokenizer was last left holding an unfinished sequence
+    pending_since: Option<Instant>,
+}
+
+impl Reader {
+    fn new() -> Reader {
+        Reader {
+            tokenizer: Tokenizer::new(),
+            pending_since: None,
         }
     }
 
-    inputs
+    // bytes is empty if nothing was read
+    fn feed(&mut self, bytes: &[u8], now: Instant) -> Vec<Vec<u8>> {
+        if !bytes.is_empty() {
+            let tokens = self.tokenizer.feed(bytes);
+            self.pending_since = self.tokenizer.is_pending().then_some(now);
+            tokens
+        } else if self.deadline().is_some_and(|d| d <= now) {
+            self.pending_since = None;
+            self.tokenizer.flush()
+        } else {
+            vec![]
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        let timeout = if self.tokenizer.in_paste() {
+            PASTE_TIMEOUT
+        } else {
+            ESCAPE_TIMEOUT
+        };
+
+        self.pending_since.map(|since| since + timeout)
+    }
 }
 
 pub(crate) struct Terminal {
-    // Modes
-    canonical_mode: termios,
+    // Input split across reads
+    reader: Reader,
+    // Key reporting pushed onto the terminal
+    protocol: Protocol,
+    mouse: MouseCapture,
 }
 
 impl Terminal {
@@ -77,50 +201,141 @@ impl Terminal {
             c_ospeed: 0,
         };
 
-        let mut raw_mode: termios;
-
         unsafe {
             tcgetattr(STDIN_FILENO, &mut canonical_mode);
-            raw_mode = canonical_mode;
-            raw_mode.c_lflag &= !(ICANON | ECHO);
         }
 
-        // Save screen
-        printlnf!("\x1b[?47h");
+        CANONICAL.get_or_init(|| canonical_mode);
+        OWNER.with(|o| o.set(true));
+        install_panic_hook();
 
-        let stdin_fd = stdin().as_raw_fd();
+        enter_raw();
 
-        unsafe {
-            // None-blocking
-            fcntl(
-                stdin_fd,
-                F_SETFL,
-                libc::fcntl(stdin_fd, F_GETFL) | O_NONBLOCK,
-            );
-
-            // Set raw mode
-            tcsetattr(STDIN_FILENO, TCSANOW, &raw_mode);
+        Terminal {
+            reader: Reader::new(),
+            protocol: Protocol::Default,
+            mouse: MouseCapture::None,
+        }
+    }
+
+    // Gives the terminal back like it was before, for stopping or handing it to another
+    // program. Input that was half read is dropped.
+    pub(crate) fn suspend(&mut self) {
+        let _ = stdout().flush();
+
+        restore();
+        self.reader = Reader::new();
+    }
+
+    // Takes the terminal again after suspend, with the same key reporting and mouse capture.
+    // The screen has to be drawn again from scratch.
+    pub(crate) fn resume(&mut self) {
+        if ACTIVE.load(Ordering::SeqCst) {
+            return;
+        }
+
+        enter_raw();
+
+        self.protocol.activate();
+        self.mouse.activate();
+        KITTY.store(self.protocol == Protocol::Kitty, Ordering::SeqCst);
+    }
+
+    pub(crate) fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol.deactivate();
+        self.protocol = protocol;
+        self.protocol.activate();
+
+        KITTY.store(protocol == Protocol::Kitty, Ordering::SeqCst);
+    }
+
+    pub(crate) fn set_mouse_capture(&mut self, mouse: MouseCapture) {
+        self.mouse.deactivate();
+        self.mouse = mouse;
+        self.mouse.activate();
+    }
+
+    // Everything read from stdin since the last poll. An escape sequence cut off at the
+    // end of a read is kept for later polls, unless nothing else arrived in time.
+    pub(crate) fn poll(&mut self) -> Vec<Input> {
+        let mut bytes = vec![];
+        let mut buf = [0; 512];
+
+        while let Ok(n @ 1..) = stdin().read(&mut buf) {
+            bytes.extend_from_slice(&buf[..n]);
         }
 
-        Terminal { canonical_mode }
+        let tokens = self.reader.feed(&bytes, Instant::now());
+
+        // Anything unrecognized is still passed on so the app can decide what to do with it
+        tokens
+            .iter()
+            .filter_map(|t| match parse(t) {
+                Ok(input) => Some(input),
+                Err(ParseError::Empty) => None,
+                Err(e) => Some(Input::Unknown(e.into_bytes())),
+            })
+            .collect()
+    }
+
+    // When an unfinished escape sequence is given up on
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.reader.deadline()
     }
 }
 
 impl Drop for Terminal {
     fn drop(&mut self) {
-        // Restore canonical mode
-        unsafe {
-            tcsetattr(STDIN_FILENO, TCSANOW, &self.canonical_mode);
-        }
+        // Anything still buffered goes out before the screen is switched back
+        let _ = stdout().flush();
+
+        restore();
+        OWNER.with(|o| o.set(false));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn panics_on_other_threads_leave_the_terminal() {
+        let restored = |owner: bool| {
+            std::thread::spawn(move || {
+                OWNER.with(|o| o.set(owner));
+
+                let mut restored = false;
+                on_panic(|| restored = true);
+                restored
+            })
+            .join()
+            .unwrap()
+        };
+
+        assert!(restored(true));
+        assert!(!restored(false));
+    }
+
+    #[test]
+    fn paste_waits_longer_than_escape() {
+        let mut reader = Reader::new();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
 
-        // Restore screen
-        printlnf!("\x1b[?47l\x1b[?25h");
+        assert!(reader.feed(b"\x1b", at(0)).is_empty());
+        assert_eq!(reader.feed(b"", at(30)), [b"\x1b"]);
 
-        // Restore default key reporting
-        Protocol::Default.activate();
+        // Stalls for a lot longer than an escape sequence may between chunks
+        assert!(reader.feed(b"\x1b[200~some", at(100)).is_empty());
+        assert!(reader.feed(b"", at(500)).is_empty());
+        assert_eq!(
+            reader.feed(b" text\x1b[201~", at(600)),
+            [b"\x1b[200~some text\x1b[201~"]
+        );
 
-        // Stop mouse stuff
-        crate::printf!("\x1b[?1002l");
-        crate::printf!("\x1b[?1006l");
+        // Given up on eventually
+        assert!(reader.feed(b"\x1b[200~cut", at(700)).is_empty());
+        assert!(reader.feed(b"", at(2000)).is_empty());
+        assert_eq!(reader.feed(b"", at(2700)), [b"\x1b[200~cut"]);
     }
 }
@@ -0,0 +1,140 @@
+use super::{App, control::Control};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+// Called like run, with the app's state available through it
+pub type TimerCallback<S> = Rc<RefCell<dyn FnMut(&mut App<S>) -> Control>>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TimerId(usize);
+
+struct Timer<S> {
+    id: TimerId,
+    due: Instant,
+    // Repeats this often if set
+    interval: Option<Duration>,
+    callback: TimerCallback<S>,
+}
+
+pub(crate) struct Timers<S> {
+    timers: Vec<Timer<S>>,
+    next_id: usize,
+}
+
+impl<S> Timers<S> {
+    pub(crate) fn new() -> Timers<S> {
+        Timers {
+            timers: vec![],
+            next_id: 0,
+        }
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        due: Instant,
+        interval: Option<Duration>,
+        callback: TimerCallback<S>,
+    ) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+
+        self.timers.push(Timer {
+            id,
+            due,
+            interval,
+            callback,
+        });
+
+        id
+    }
+
+    // Returns whether the timer was still waiting
+    pub(crate) fn cancel(&mut self, id: TimerId) -> bool {
+        let len = self.timers.len();
+        self.timers.retain(|t| t.id != id);
+        self.timers.len() != len
+    }
+
+    // Callbacks of the timers due by now, earliest first. One-shot timers are removed and
+    // repeating ones moved to their next time.
+    pub(crate) fn take_due(&mut self, now: Instant) -> Vec<TimerCallback<S>> {
+        let mut due: Vec<(Instant, TimerCallback<S>)> = vec![];
+
+        self.timers.retain_mut(|timer| {
+            if timer.due > now {
+                return true;
+            }
+
+            due.push((timer.due, timer.callback.clone()));
+
+            match timer.interval {
+                Some(interval) => {
+                    // A late repeating timer fires once instead of catching up
+                    timer.due = (timer.due + interval).max(now);
+                    true
+                }
+                None => false,
+            }
+        });
+
+        due.sort_by_key(|(at, _)| *at);
+        due.into_iter().map(|(_, callback)| callback).collect()
+    }
+
+    pub(crate) fn next(&self) -> Option<Instant> {
+        self.timers.iter().map(|t| t.due).min()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn callback(control: Control) -> TimerCallback<()> {
+        let mut control = Some(control);
+        Rc::new(RefCell::new(move |_: &mut App| {
+            control.take().unwrap_or(Control::Continue)
+        }))
+    }
+
+    #[test]
+    fn one_shot_and_repeating() {
+        let mut timers = Timers::new();
+        let now = Instant::now();
+
+        timers.add(now + MS * 10, None, callback(Control::Quit(1)));
+        timers.add(now + MS * 5, Some(MS * 20), callback(Control::Quit(2)));
+        assert_eq!(timers.next(), Some(now + MS * 5));
+
+        assert!(timers.take_due(now).is_empty());
+
+        let mut app = App::headless(10, 10);
+        let due = timers.take_due(now + MS * 10);
+        // Repeating timer is earlier, so it comes first
+        let controls: Vec<_> = due.iter().map(|f| (f.borrow_mut())(&mut app)).collect();
+        assert_eq!(controls, [Control::Quit(2), Control::Quit(1)]);
+        assert_eq!(timers.next(), Some(now + MS * 25));
+
+        // Skipped ticks don't pile up
+        assert_eq!(timers.take_due(now + MS * 100).len(), 1);
+        assert_eq!(timers.next(), Some(now + MS * 100));
+    }
+
+    #[test]
+    fn cancel() {
+        let mut timers = Timers::new();
+        let now = Instant::now();
+
+        let id = timers.add(now, Some(MS), callback(Control::Continue));
+        assert!(timers.cancel(id));
+        assert!(!timers.cancel(id));
+
+        assert!(timers.take_due(now + MS * 10).is_empty());
+        assert_eq!(timers.next(), None);
+    }
+}
@@ -0,0 +1,155 @@
+use libc::{EINTR, O_NONBLOCK, POLLIN, STDIN_FILENO, c_int, c_void, pollfd};
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::Arc,
+    time::Duration,
+};
+
+// Wakes the main loop from another thread, like when background work finished and the screen
+// should show it. The app steps at least once after a wake, several wakes before it gets to
+// them may make a single step.
+#[derive(Clone, Debug)]
+pub struct Waker {
+    fd: Arc<OwnedFd>,
+}
+
+impl Waker {
+    pub fn wake(&self) {
+        // A full pipe already wakes the loop, and a closed one means the app is gone
+        unsafe {
+            libc::write(self.fd.as_raw_fd(), [1u8].as_ptr() as *const c_void, 1);
+        }
+    }
+
+    // For signal handlers, which can only write to it
+    pub(crate) fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+// Self-pipe the main loop waits on next to stdin
+pub(crate) struct WakePipe {
+    read: OwnedFd,
+    waker: Waker,
+}
+
+impl WakePipe {
+    pub(crate) fn new() -> WakePipe {
+        let mut fds: [c_int; 2] = [0; 2];
+
+        unsafe {
+            if libc::pipe(fds.as_mut_ptr()) != 0 {
+                panic!("couldn't create wake pipe: {}", io::Error::last_os_error());
+            }
+
+            for fd in fds {
+                libc::fcntl(
+                    fd,
+                    libc::F_SETFL,
+                    libc::fcntl(fd, libc::F_GETFL) | O_NONBLOCK,
+                );
+            }
+
+            WakePipe {
+                read: OwnedFd::from_raw_fd(fds[0]),
+                waker: Waker {
+                    fd: Arc::new(OwnedFd::from_raw_fd(fds[1])),
+                },
+            }
+        }
+    }
+
+    pub(crate) fn waker(&self) -> Waker {
+        self.waker.clone()
+    }
+
+    // Blocks until stdin has input (if watched), a waker was used, the timeout passed or a
+    // signal arrived. None waits for as long as it takes. Returns whether a waker was used.
+    pub(crate) fn wait(&self, stdin: bool, timeout: Option<Duration>) -> bool {
+        let mut fds = vec![pollfd {
+            fd: self.read.as_raw_fd(),
+            events: POLLIN,
+            revents: 0,
+        }];
+
+        if stdin {
+            fds.push(pollfd {
+                fd: STDIN_FILENO,
+                events: POLLIN,
+                revents: 0,
+            });
+        }
+
+        // Rounded up so a deadline isn't woken for just before it's due
+        let timeout = timeout.map_or(-1, |t| {
+            t.as_nanos().div_ceil(1_000_000).min(c_int::MAX as u128) as c_int
+        });
+
+        let n = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as _, timeout) };
+
+        if n < 0 && io::Error::last_os_error().raw_os_error() != Some(EINTR) {
+            return false;
+        }
+
+        let woken = fds[0].revents & POLLIN != 0;
+
+        if woken {
+            self.drain();
+        }
+
+        woken
+    }
+
+    fn drain(&self) {
+        let mut buf = [0u8; 64];
+
+        while unsafe {
+            libc::read(
+                self.read.as_raw_fd(),
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len(),
+            )
+        } > 0
+        {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn wake_from_thread() {
+        let pipe = WakePipe::new();
+        let waker = pipe.waker();
+
+        std::thread::spawn(move || waker.wake()).join().unwrap();
+
+        assert!(pipe.wait(false, Some(Duration::from_secs(5))));
+        // Drained, so the next wait times out
+        assert!(!pipe.wait(false, Some(Duration::ZERO)));
+    }
+
+    #[test]
+    fn timeout() {
+        let pipe = WakePipe::new();
+        let start = Instant::now();
+
+        assert!(!pipe.wait(false, Some(Duration::from_millis(20))));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn wakes_pile_up() {
+        let pipe = WakePipe::new();
+
+        for _ in 0..1000 {
+            pipe.waker().wake();
+        }
+
+        assert!(pipe.wait(false, None));
+        assert!(!pipe.wait(false, Some(Duration::ZERO)));
+    }
+}
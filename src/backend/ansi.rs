@@ -0,0 +1,129 @@
+use super::Backend;
+use crate::{
+    app::terminal::termsz,
+    draw::buffer::Cell,
+    printf,
+    style::{PrintableStyle, Style},
+};
+use std::{any::Any, fmt::Write};
+
+// Writes to stdout with escape sequences
+pub struct AnsiBackend {
+    out: String,
+}
+
+impl AnsiBackend {
+    pub fn new() -> AnsiBackend {
+        AnsiBackend { out: String::new() }
+    }
+}
+
+impl Backend for AnsiBackend {
+    fn size(&self) -> (usize, usize) {
+        termsz()
+    }
+
+    fn draw(&mut self, cells: Vec<(usize, usize, &Cell)>) {
+        self.out.push_str(&encode(cells));
+    }
+
+    fn clear(&mut self) {
+        self.out.push_str("\x1b[0m\x1b[2J");
+    }
+
+    fn flush(&mut self) {
+        if !self.out.is_empty() {
+            printf!("{}", self.out);
+            self.out.clear();
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Escape sequences to draw the given cells, moving the cursor only when a cell does not
+// directly follow the last one and changing style only when it differs
+fn encode(cells: Vec<(usize, usize, &Cell)>) -> String {
+    let mut out = String::new();
+    let mut at: Option<(usize, usize)> = None;
+    let mut style: Option<Style> = None;
+
+    for (x, y, cell) in cells {
+        if cell.is_continuation() {
+            continue;
+        }
+
+        if at != Some((x, y)) {
+            _ = write!(out, "\x1b[{};{}H", y + 1, x + 1);
+        }
+
+        if style != Some(cell.style) {
+            out.push_str(&cell.style.print());
+            style = Some(cell.style);
+        }
+
+        out.push(cell.ch);
+        at = Some((x + cell.width(), y));
+    }
+
+    if style.is_some() {
+        out.push_str("\x1b[0m");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{draw::buffer::Buffer, style::color::Color, unit::Point};
+
+    #[test]
+    fn encode_skips_redundant_moves_and_styles() {
+        let mut buf = Buffer::new(4, 2);
+        let prev = buf.clone();
+        buf.go(Point::new(1, 1));
+        buf.print("ab");
+        buf.go(Point::new(4, 2));
+        buf.set_style(Color::Red);
+        buf.print("c");
+
+        assert_eq!(
+            encode(buf.diff(&prev)),
+            "\x1b[1;1H\x1b[0m\x1b[39m\x1b[49mab\x1b[2;4H\x1b[0m\x1b[31m\x1b[49mc\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn encode_wide_chars_once() {
+        let mut buf = Buffer::new(4, 1);
+        let prev = buf.clone();
+        buf.go(Point::new(1, 1));
+        buf.print("漢a");
+
+        assert_eq!(
+            encode(buf.diff(&prev)),
+            "\x1b[1;1H\x1b[0m\x1b[39m\x1b[49m漢a\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn encode_nothing() {
+        let buf = Buffer::new(2, 2);
+        assert_eq!(encode(buf.diff(&buf.clone())), "");
+    }
+
+    #[test]
+    fn draw_buffers_until_flush() {
+        let mut backend = AnsiBackend::new();
+        let buf = Buffer::new(1, 1);
+        backend.draw(buf.diff(&Buffer::new(0, 0)));
+        assert_eq!(backend.out, "\x1b[1;1H\x1b[0m\x1b[39m\x1b[49m \x1b[0m");
+    }
+}
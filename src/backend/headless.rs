@@ -0,0 +1,92 @@
+use super::Backend;
+use crate::draw::buffer::{Buffer, Cell};
+use std::any::Any;
+
+// In-memory grid, for rendering without a terminal attached
+pub struct HeadlessBackend {
+    grid: Buffer,
+    flushes: usize,
+}
+
+impl HeadlessBackend {
+    pub fn new(width: usize, height: usize) -> HeadlessBackend {
+        HeadlessBackend {
+            grid: Buffer::new(width, height),
+            flushes: 0,
+        }
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.grid.resize(width, height);
+    }
+
+    // Everything drawn so far
+    pub fn buffer(&self) -> &Buffer {
+        &self.grid
+    }
+
+    pub fn flushes(&self) -> usize {
+        self.flushes
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn size(&self) -> (usize, usize) {
+        self.grid.size()
+    }
+
+    fn draw(&mut self, cells: Vec<(usize, usize, &Cell)>) {
+        for (x, y, cell) in cells {
+            if let Some(c) = self.grid.get_mut(x, y) {
+                *c = *cell;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.grid.reset();
+        self.grid.clear();
+    }
+
+    fn flush(&mut self) {
+        self.flushes += 1;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::unit::Point;
+
+    #[test]
+    fn draw_and_clear() {
+        let mut backend = HeadlessBackend::new(3, 1);
+        let mut buf = Buffer::new(3, 1);
+        buf.go(Point::new(2, 1));
+        buf.print("x");
+
+        backend.draw(buf.diff(&Buffer::new(3, 1)));
+        assert_eq!(backend.buffer().get(1, 0).unwrap().ch, 'x');
+
+        backend.clear();
+        assert_eq!(backend.buffer(), &Buffer::new(3, 1));
+    }
+
+    #[test]
+    fn draw_clips_outside() {
+        let mut backend = HeadlessBackend::new(1, 1);
+        let buf = Buffer::new(2, 2);
+        backend.draw(buf.diff(&Buffer::new(0, 0)));
+        backend.flush();
+        assert_eq!(backend.buffer().size(), (1, 1));
+        assert_eq!(backend.flushes(), 1);
+    }
+}
@@ -0,0 +1,48 @@
+pub mod ansi;
+pub mod headless;
+
+use crate::draw::buffer::Cell;
+use std::any::Any;
+
+// Where finished frames end up. Screen hands over only the cells that changed since the
+// last flush, positions are 0-based (x, y).
+pub trait Backend {
+    // (x, y)
+    fn size(&self) -> (usize, usize);
+
+    fn draw(&mut self, cells: Vec<(usize, usize, &Cell)>);
+
+    fn clear(&mut self);
+
+    fn flush(&mut self);
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<B: Backend + ?Sized + 'static> Backend for Box<B> {
+    fn size(&self) -> (usize, usize) {
+        (**self).size()
+    }
+
+    fn draw(&mut self, cells: Vec<(usize, usize, &Cell)>) {
+        (**self).draw(cells);
+    }
+
+    fn clear(&mut self) {
+        (**self).clear();
+    }
+
+    fn flush(&mut self) {
+        (**self).flush();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        (**self).as_any()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        (**self).as_any_mut()
+    }
+}
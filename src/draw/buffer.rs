@@ -0,0 +1,344 @@
+use crate::{
+    style::{PrintableStyle, Style},
+    unit::Point,
+};
+use std::fmt::Display;
+use unicode_width::UnicodeWidthChar;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Cell {
+    pub fn new(ch: char, style: Style) -> Cell {
+        Cell { ch, style }
+    }
+
+    pub fn blank(style: Style) -> Cell {
+        Cell { ch: ' ', style }
+    }
+
+    // Right half of a wide char, drawn by the cell to its left
+    pub fn continuation(style: Style) -> Cell {
+        Cell { ch: '\0', style }
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.ch == '\0'
+    }
+
+    // Columns the char takes up in the terminal
+    pub fn width(&self) -> usize {
+        self.ch.width().unwrap_or(0)
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::blank(Style::new())
+    }
+}
+
+// Grid of styled cells that widgets draw into instead of the terminal.
+// Drawing works like printing to a terminal: styles set on the pen stay until changed and
+// positions are 1-based, so anything that used cursor::go and printf! maps over directly.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Buffer {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    pen: Style,
+    cursor: (usize, usize),
+}
+
+impl Buffer {
+    pub fn new(width: usize, height: usize) -> Buffer {
+        Buffer {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+            pen: Style::new(),
+            cursor: (0, 0),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // (x, y)
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.cells = vec![Cell::blank(self.pen); width * height];
+        self.cursor = (0, 0);
+    }
+
+    // Fill every cell with a blank using the current pen, same as \x1b[2J
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::blank(self.pen));
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    // 0-based
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    // 0-based
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get_mut(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn pen(&self) -> Style {
+        self.pen
+    }
+
+    pub fn set_style(&mut self, s: impl PrintableStyle) {
+        s.apply(&mut self.pen);
+    }
+
+    pub fn reset(&mut self) {
+        self.pen = Style::new();
+    }
+
+    pub fn go(&mut self, p: Point) {
+        self.cursor = (p.x.calc().saturating_sub(1), p.y.calc().saturating_sub(1));
+    }
+
+    // Write at the cursor and advance it by the width of what was written. Wide chars take up
+    // the next cell as well and zero-width ones are left out. Anything past the right edge is
+    // clipped, wide chars that don't fit entirely included.
+    pub fn print(&mut self, s: impl Display) {
+        let pen = self.pen;
+        let (mut x, y) = self.cursor;
+
+        for ch in s.to_string().chars() {
+            let width = ch.width().unwrap_or(0);
+
+            if width == 0 {
+                continue;
+            }
+
+            if x + width <= self.width {
+                for i in x..x + width {
+                    self.split_wide(i, y);
+                }
+
+                self.set(x, y, Cell::new(ch, pen));
+
+                if width == 2 {
+                    self.set(x + 1, y, Cell::continuation(pen));
+                }
+            }
+
+            x += width;
+        }
+
+        self.cursor.0 = x;
+    }
+
+    // Blanks what's left of a wide char about to be partly overwritten at (x, y), as the
+    // terminal would
+    fn split_wide(&mut self, x: usize, y: usize) {
+        let other = match self.get(x, y) {
+            Some(cell) if cell.is_continuation() => x.checked_sub(1),
+            Some(_) if self.get(x + 1, y).is_some_and(|c| c.is_continuation()) => Some(x + 1),
+            _ => None,
+        };
+
+        if let Some(cell) = other.and_then(|other| self.get_mut(other, y)) {
+            *cell = Cell::blank(cell.style);
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if let Some(c) = self.get_mut(x, y) {
+            *c = cell;
+        }
+    }
+
+    pub fn put(&mut self, p: Point, ch: char) {
+        self.go(p);
+        self.print(ch);
+    }
+
+    // Cells that differ from prev as (x, y, cell), 0-based and in row order
+    pub fn diff<'a>(&'a self, prev: &Buffer) -> Vec<(usize, usize, &'a Cell)> {
+        if self.size() != prev.size() {
+            return self
+                .cells
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i % self.width, i / self.width, c))
+                .collect();
+        }
+
+        self.cells
+            .iter()
+            .zip(prev.cells.iter())
+            .enumerate()
+            .filter(|(_, (c, p))| c != p)
+            .map(|(i, (c, _))| (i % self.width, i / self.width, c))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style::{
+        color::{Color, ColorBG},
+        text::TextStyle,
+    };
+
+    fn row(buf: &Buffer, y: usize) -> String {
+        (0..buf.width())
+            .map(|x| buf.get(x, y).unwrap().ch)
+            .collect()
+    }
+
+    #[test]
+    fn new_is_blank() {
+        let buf = Buffer::new(4, 2);
+        assert_eq!(buf.size(), (4, 2));
+        assert_eq!(buf.cells().len(), 8);
+        assert!(buf.cells().iter().all(|c| *c == Cell::default()));
+    }
+
+    #[test]
+    fn print_at_one_based_position() {
+        let mut buf = Buffer::new(5, 2);
+        buf.go(Point::new(2, 2));
+        buf.print("ab");
+        assert_eq!(row(&buf, 0), "     ");
+        assert_eq!(row(&buf, 1), " ab  ");
+    }
+
+    #[test]
+    fn print_continues_from_cursor() {
+        let mut buf = Buffer::new(5, 1);
+        buf.go(Point::new(1, 1));
+        buf.print("ab");
+        buf.print('c');
+        assert_eq!(row(&buf, 0), "abc  ");
+    }
+
+    #[test]
+    fn print_clips_outside() {
+        let mut buf = Buffer::new(3, 1);
+        buf.go(Point::new(2, 1));
+        buf.print("abcd");
+        assert_eq!(row(&buf, 0), " ab");
+
+        buf.go(Point::new(1, 5));
+        buf.print("zzz");
+        assert_eq!(row(&buf, 0), " ab");
+    }
+
+    #[test]
+    fn print_wide_and_zero_width() {
+        let mut buf = Buffer::new(6, 1);
+        buf.go(Point::new(1, 1));
+        buf.print("漢e\u{301}字");
+        assert_eq!(row(&buf, 0), "漢\0e字\0 ");
+        assert!(buf.get(1, 0).unwrap().is_continuation());
+
+        // Cursor went by columns, not chars
+        buf.print('x');
+        assert_eq!(row(&buf, 0), "漢\0e字\0x");
+    }
+
+    #[test]
+    fn print_over_half_of_wide() {
+        let mut buf = Buffer::new(5, 1);
+        buf.go(Point::new(1, 1));
+        buf.print("漢字");
+        buf.go(Point::new(2, 1));
+        buf.print('a');
+        assert_eq!(row(&buf, 0), " a字\0 ");
+
+        buf.go(Point::new(3, 1));
+        buf.print('b');
+        assert_eq!(row(&buf, 0), " ab  ");
+
+        // Doesn't fit at the edge
+        buf.go(Point::new(5, 1));
+        buf.print('字');
+        assert_eq!(row(&buf, 0), " ab  ");
+    }
+
+    #[test]
+    fn pen_styles_cells() {
+        let mut buf = Buffer::new(2, 1);
+        buf.set_style(Color::Red);
+        buf.set_style(TextStyle::Bold);
+        buf.go(Point::new(1, 1));
+        buf.print("a");
+        buf.reset();
+        buf.print("b");
+
+        let a = buf.get(0, 0).unwrap().style;
+        assert_eq!(a.fg, Color::Red);
+        assert!(a.has(TextStyle::Bold));
+        assert_eq!(buf.get(1, 0).unwrap().style, Style::new());
+    }
+
+    #[test]
+    fn clear_uses_pen() {
+        let mut buf = Buffer::new(2, 2);
+        buf.set_style(ColorBG::Blue);
+        buf.clear();
+        assert!(buf.cells().iter().all(|c| c.style.bg == ColorBG::Blue));
+    }
+
+    #[test]
+    fn diff_only_changed() {
+        let prev = Buffer::new(3, 2);
+        let mut buf = prev.clone();
+        buf.go(Point::new(3, 2));
+        buf.print("x");
+
+        let d = buf.diff(&prev);
+        assert_eq!(d.len(), 1);
+        assert_eq!((d[0].0, d[0].1, d[0].2.ch), (2, 1, 'x'));
+        assert!(buf.diff(&buf.clone()).is_empty());
+    }
+
+    #[test]
+    fn diff_style_change() {
+        let prev = Buffer::new(1, 1);
+        let mut buf = prev.clone();
+        buf.set_style(ColorBG::Red);
+        buf.clear();
+        assert_eq!(buf.diff(&prev).len(), 1);
+    }
+
+    #[test]
+    fn diff_resized_is_everything() {
+        let prev = Buffer::new(2, 2);
+        let buf = Buffer::new(3, 2);
+        assert_eq!(buf.diff(&prev).len(), 6);
+    }
+}
@@ -1,7 +1,4 @@
-use crate::{
-    printf,
-    style::color::{Color, ColorBG},
-};
+use crate::printf;
 
 pub fn show() {
     printf!("\x1b[?25h");
@@ -1,39 +1,61 @@
 pub mod box_char;
+pub mod buffer;
 pub mod cursor;
+pub mod screen;
 
 use crate::{
-    app::get_tsz,
     printf,
-    style::{self, align::AlignX, line::Line, set_style, text::TextStyle},
+    style::{align::AlignX, line::Line, text::TextStyle},
     unit::{Point, Unit},
     widget::attr::Attr,
 };
 use box_char::BoxChar;
+use buffer::Buffer;
 
 pub fn clear() {
     printf!("\x1b[2J");
 }
 
-pub(crate) fn draw_frame(attr: &Attr) {
+// Outline of a w x h box with its top left corner at anchor
+fn outline(buf: &mut Buffer, anchor: Point, (w, h): (usize, usize), chars: [BoxChar; 6]) {
+    let [hc, vc, tl, tr, br, bl] = chars;
+
+    if w < 2 || h < 2 {
+        return;
+    }
+
+    buf.go(anchor);
+    buf.print(format!("{}{}{}", tl, format!("{}", hc).repeat(w - 2), tr));
+
+    for y in 1..h - 1 {
+        buf.put((anchor, 0, y).into(), vc.to_char());
+        buf.put((anchor, w - 1, y).into(), vc.to_char());
+    }
+
+    buf.go((anchor, 0, h - 1).into());
+    buf.print(format!("{}{}{}", bl, format!("{}", hc).repeat(w - 2), br));
+}
+
+pub(crate) fn draw_frame(buf: &mut Buffer, attr: &Attr) {
     if !attr.hide_border {
         let anchor = Point::new(1, 1);
-        let (w, h) = get_tsz();
-
-        set_style(attr.border_color);
-        set_style(attr.border_fill);
-
-        cursor::go(anchor);
-
-        printf!(
-            "{}{}{}\x1b[1B{}{}\x1b[2D{}{}\x1b[1D\x1b[1A{}",
-            BoxChar::DoubleTL,
-            format!("{}", BoxChar::DoubleH).repeat(w - 2),
-            BoxChar::DoubleTR,
-            format!("{}\x1b[1B", BoxChar::DoubleV).repeat(h - 2),
-            BoxChar::DoubleBR,
-            format!("{}\x1b[2D", BoxChar::DoubleH).repeat(w - 2),
-            BoxChar::DoubleBL,
-            format!("{}\x1b[1A\x1b[1D", BoxChar::DoubleV).repeat(h - 2),
+        let (w, h) = buf.size();
+
+        buf.set_style(attr.border_color);
+        buf.set_style(attr.border_fill);
+
+        outline(
+            buf,
+            anchor,
+            (w, h),
+            [
+                BoxChar::DoubleH,
+                BoxChar::DoubleV,
+                BoxChar::DoubleTL,
+                BoxChar::DoubleTR,
+                BoxChar::DoubleBR,
+                BoxChar::DoubleBL,
+            ],
         );
 
         if !attr.hide_title {
@@ -45,10 +67,10 @@ pub(crate) fn draw_frame(attr: &Attr) {
                 AlignX::Right => w - (title.len() + 5),
             };
 
-            set_style(TextStyle::Bold);
+            buf.set_style(TextStyle::Bold);
 
-            cursor::go((anchor, Unit::CoR(x_offset), Unit::CoR(0)).into());
-            printf!("╣ {} ╠", title);
+            buf.go((anchor, Unit::CoR(x_offset), Unit::CoR(0)).into());
+            buf.print(format!("╣ {} ╠", title));
         }
 
         if !attr.hide_binds {
@@ -60,15 +82,15 @@ pub(crate) fn draw_frame(attr: &Attr) {
                 AlignX::Right => w - (l + 1),
             };
 
-            cursor::go((anchor, x_offset, h - 1).into());
-            printf!("{}", attr.binds);
+            buf.go((anchor, x_offset, h - 1).into());
+            buf.print(&attr.binds);
         }
 
-        style::reset();
+        buf.reset();
     }
 }
 
-pub fn draw_box(anchor: Point, attr: &Attr, line: Line) {
+pub fn draw_box(buf: &mut Buffer, anchor: Point, attr: &Attr, line: Line) {
     if !attr.hide_border {
         let w = attr.width.calc();
         let h = attr.height.calc();
@@ -107,23 +129,11 @@ pub fn draw_box(anchor: Point, attr: &Attr, line: Line) {
             bl = BoxChar::ArcBL;
         }
 
-        cursor::go(anchor);
-
-        printf!(
-            "{}{}{}\x1b[1D\x1b[1B{}{}\x1b[2D{}{}\x1b[1D\x1b[1A{}",
-            tl,
-            format!("{}", hc).repeat(w - 2),
-            tr,
-            format!("{}\x1b[1B\x1b[1D", vc).repeat(h - 2),
-            br,
-            format!("{}\x1b[2D", hc).repeat(w - 2),
-            bl,
-            format!("{}\x1b[1A\x1b[1D", vc).repeat(h - 2),
-        );
+        outline(buf, anchor, (w, h), [hc, vc, tl, tr, br, bl]);
     }
 }
 
-pub fn draw_title(anchor: Point, attr: &Attr) {
+pub fn draw_title(buf: &mut Buffer, anchor: Point, attr: &Attr) {
     if !attr.hide_border && !attr.hide_title {
         let width = attr.width.calc();
         let title = &attr.title;
@@ -134,12 +144,12 @@ pub fn draw_title(anchor: Point, attr: &Attr) {
             AlignX::Right => width - (title.len() + 5),
         };
 
-        cursor::go((anchor, x_offset, 0).into());
-        printf!("┤ {} ├", title);
+        buf.go((anchor, x_offset, 0).into());
+        buf.print(format!("┤ {} ├", title));
     }
 }
 
-pub fn draw_binds(anchor: Point, attr: &Attr) {
+pub fn draw_binds(buf: &mut Buffer, anchor: Point, attr: &Attr) {
     if !attr.hide_border && !attr.hide_binds {
         let width = attr.width.calc();
         let l = attr.binds.len();
@@ -150,7 +160,64 @@ pub fn draw_binds(anchor: Point, attr: &Attr) {
             AlignX::Right => width - (l + 1),
         };
 
-        cursor::go((anchor, x_offset, attr.height.calc() - 1).into());
-        printf!("{}", attr.binds);
+        buf.go((anchor, x_offset, attr.height.calc() - 1).into());
+        buf.print(&attr.binds);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(buf: &Buffer) -> Vec<String> {
+        (0..buf.height())
+            .map(|y| {
+                (0..buf.width())
+                    .map(|x| buf.get(x, y).unwrap().ch)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn box_outline() {
+        let mut buf = Buffer::new(6, 4);
+        let mut attr = Attr::new();
+        attr.size(4usize, 3usize);
+
+        draw_box(&mut buf, Point::new(2, 1), &attr, Line::Light);
+
+        assert_eq!(text(&buf), [" ┌──┐ ", " │  │ ", " └──┘ ", "      "]);
+    }
+
+    #[test]
+    fn box_arc_and_title() {
+        let mut buf = Buffer::new(9, 3);
+        let mut attr = Attr::new();
+        attr.size(9usize, 3usize).arc().title("t");
+
+        draw_box(&mut buf, Point::new(1, 1), &attr, Line::Heavy);
+        draw_title(&mut buf, Point::new(1, 1), &attr);
+
+        assert_eq!(text(&buf), ["╭┤ t ├━━╮", "┃       ┃", "╰━━━━━━━╯"]);
+    }
+
+    #[test]
+    fn hidden_border_draws_nothing() {
+        let mut buf = Buffer::new(4, 3);
+        let mut attr = Attr::new();
+        attr.size(4usize, 3usize).hide_border();
+
+        draw_box(&mut buf, Point::new(1, 1), &attr, Line::Light);
+
+        assert_eq!(buf, Buffer::new(4, 3));
+    }
+
+    #[test]
+    fn frame_fills_buffer() {
+        let mut buf = Buffer::new(4, 3);
+        draw_frame(&mut buf, &Attr::new());
+
+        assert_eq!(text(&buf), ["╔══╗", "║  ║", "╚══╝"]);
     }
 }
@@ -0,0 +1,240 @@
+use super::buffer::Buffer;
+use crate::{backend::Backend, panel::frame::Frame, widget::attr::Dirty};
+
+// Double buffer. Frames are drawn into `current` and only the cells that differ from
+// `previous` (what the backend already shows) are passed on.
+pub struct Screen<B: Backend> {
+    backend: B,
+    current: Buffer,
+    previous: Buffer,
+    invalidated: bool,
+}
+
+impl<B: Backend> Screen<B> {
+    pub fn new(backend: B) -> Screen<B> {
+        Screen {
+            backend,
+            current: Buffer::new(0, 0),
+            previous: Buffer::new(0, 0),
+            invalidated: true,
+        }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn buffer(&mut self) -> &mut Buffer {
+        &mut self.current
+    }
+
+    // What the backend is showing after the last flush
+    pub fn last_frame(&self) -> &Buffer {
+        &self.previous
+    }
+
+    // Force the next flush to write every cell
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    // Start a new frame at the backend's current size
+    pub fn begin(&mut self) {
+        let size = self.backend.size();
+
+        if self.current.size() != size {
+            self.current.resize(size.0, size.1);
+            self.invalidated = true;
+        }
+
+        self.current.reset();
+    }
+
+    pub fn flush(&mut self) {
+        if self.invalidated {
+            self.backend.clear();
+            self.backend.draw(self.current.diff(&Buffer::new(0, 0)));
+        } else {
+            self.backend.draw(self.current.diff(&self.previous));
+        }
+
+        self.backend.flush();
+
+        self.invalidated = false;
+        self.previous.clone_from(&self.current);
+    }
+
+    // Draws what changed in the frame since the last render over the last frame, or nothing
+    // if nothing did. Returns whether anything was drawn.
+    pub fn render(&mut self, frame: &mut Frame) -> bool {
+        self.begin();
+
+        if self.invalidated {
+            frame.attr.mark(Dirty::Paint);
+        }
+
+        if !frame.settle() {
+            return false;
+        }
+
+        let buf = &mut self.current;
+
+        if frame.attr.is_dirty() {
+            buf.set_style(frame.attr.fill);
+            buf.clear();
+        }
+
+        frame.render(buf);
+
+        self.flush();
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        backend::headless::HeadlessBackend,
+        impl_widget_base,
+        panel::Panel,
+        testing::snapshot::Snapshot,
+        unit::Point,
+        widget::{Widget, attr::Attr, container::Container, label::Label},
+    };
+    use std::any::Any;
+
+    // Counts how often it was drawn
+    #[derive(Clone, PartialEq)]
+    struct Counter {
+        attr: Attr,
+        draws: usize,
+    }
+
+    impl Counter {
+        fn new(tag: &str) -> Box<Counter> {
+            Box::new(Counter {
+                attr: Attr::new().tag(tag).size(3usize, 3usize).to_owned(),
+                draws: 0,
+            })
+        }
+    }
+
+    impl_widget_base!(Counter);
+
+    impl Widget for Counter {
+        fn render(&mut self, _: Point, _: &mut Buffer) {
+            self.draws += 1;
+        }
+    }
+
+    fn draws(frame: &mut Frame, tag: &str) -> usize {
+        let widget = frame.get_child(tag).unwrap();
+        widget.as_any().downcast_ref::<Counter>().unwrap().draws
+    }
+
+    fn text(screen: &Screen<HeadlessBackend>) -> String {
+        Snapshot::new(screen.backend().buffer().clone()).text()
+    }
+
+    #[test]
+    fn begin_resizes_and_invalidates() {
+        let mut screen = Screen::new(HeadlessBackend::new(3, 2));
+        screen.invalidated = false;
+        screen.begin();
+        assert_eq!(screen.buffer().size(), (3, 2));
+        assert!(screen.invalidated);
+    }
+
+    #[test]
+    fn flush_reaches_backend() {
+        let mut screen = Screen::new(HeadlessBackend::new(3, 1));
+        screen.begin();
+        screen.buffer().go(Point::new(1, 1));
+        screen.buffer().print("abc");
+        screen.flush();
+
+        assert_eq!(screen.backend().buffer().cells(), screen.previous.cells());
+        assert_eq!(screen.backend().flushes(), 1);
+
+        screen.begin();
+        screen.buffer().go(Point::new(2, 1));
+        screen.buffer().print("x");
+        screen.flush();
+
+        assert_eq!(screen.backend().buffer().get(0, 0).unwrap().ch, 'a');
+        assert_eq!(screen.backend().buffer().get(1, 0).unwrap().ch, 'x');
+    }
+
+    #[test]
+    fn render_frame_without_terminal() {
+        let mut screen = Screen::new(HeadlessBackend::new(4, 3));
+        screen.render(&mut Frame::new(None));
+
+        let grid = screen.backend().buffer();
+        assert_eq!(grid.get(0, 0).unwrap().ch, '╔');
+        assert_eq!(grid.get(3, 2).unwrap().ch, '╝');
+    }
+
+    #[test]
+    fn idle_frames_are_skipped() {
+        let mut frame = Frame::new(None);
+        frame.add(Label::new("a", Attr::new().size(3usize, 3usize).wrap()));
+
+        let mut screen = Screen::new(HeadlessBackend::new(8, 6));
+        assert!(screen.render(&mut frame));
+        assert!(!screen.render(&mut frame));
+        assert_eq!(screen.backend().flushes(), 1);
+
+        screen.invalidate();
+        assert!(screen.render(&mut frame));
+    }
+
+    #[test]
+    fn only_changed_widgets_are_drawn() {
+        let mut container = Container::new(Attr::new().tag("c").wrap());
+        container.addm(vec![Counter::new("a"), Counter::new("b")]);
+
+        let mut frame = Frame::new(None);
+        frame.addm(vec![container, Counter::new("d")]);
+
+        let mut screen = Screen::new(HeadlessBackend::new(20, 20));
+        screen.render(&mut frame);
+
+        frame.get_child("b").unwrap().style_mut().redraw();
+        assert!(screen.render(&mut frame));
+        assert_eq!(["a", "b", "d"].map(|t| draws(&mut frame, t)), [1, 2, 1]);
+
+        // Growing a widget moves its neighbours, so the container is drawn again
+        frame.get_child("a").unwrap().style_mut().height(4usize);
+        screen.render(&mut frame);
+        assert_eq!(["a", "b", "d"].map(|t| draws(&mut frame, t)), [2, 3, 1]);
+    }
+
+    #[test]
+    fn changed_widgets_are_cleared_first() {
+        let mut frame = Frame::new(Attr::new().hide_border().wrap());
+        frame.addm(vec![
+            Label::new("abc", Attr::new().tag("l").size(5usize, 3usize).wrap()),
+            Label::new("x", Attr::new().tag("x").size(3usize, 3usize).wrap()),
+        ]);
+
+        let mut screen = Screen::new(HeadlessBackend::new(7, 9));
+        screen.render(&mut frame);
+
+        let label = frame.get_child("l").unwrap();
+        label.as_any_mut().downcast_mut::<Label>().unwrap().text = "d".into();
+        label.style_mut().redraw();
+        screen.render(&mut frame);
+        assert_eq!(text(&screen).lines().nth(3), Some("  │d  │"));
+
+        // Hidden widgets leave a blank behind
+        frame.get_child("x").unwrap().style_mut().hide = true;
+        assert!(screen.render(&mut frame));
+        assert!(text(&screen).lines().skip(5).all(|l| l.trim().is_empty()));
+    }
+}
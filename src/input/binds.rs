@@ -1,22 +1,49 @@
-use super::Input;
-use std::fmt::Display;
+use super::{Input, sequence::Sequence};
+use std::{any::Any, fmt::Debug, fmt::Display};
+use unicode_width::UnicodeWidthStr;
+
+// Value a bind triggers, handled by whatever was registered for its type with App::on
+pub trait Action: Any + Debug + 'static {
+    fn as_any(&self) -> &dyn Any;
+
+    fn eq(&self, other: &dyn Action) -> bool;
+
+    fn aclone(&self) -> Box<dyn Action>;
+}
+
+impl Clone for Box<dyn Action> {
+    fn clone(&self) -> Box<dyn Action> {
+        self.aclone()
+    }
+}
+
+impl PartialEq for Box<dyn Action> {
+    fn eq(&self, other: &Box<dyn Action>) -> bool {
+        Action::eq(self.as_ref(), other.as_ref())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bind {
+    pub sequence: Sequence,
+    // Shown in the border
+    pub label: String,
+    pub action: Option<Box<dyn Action>>,
+}
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Binds {
-    binds: Vec<Input>,
-    actions: Vec<String>,
+    binds: Vec<Bind>,
 }
 
 impl Binds {
     pub fn new() -> Self {
-        Binds {
-            binds: vec![],
-            actions: vec![],
-        }
+        Binds { binds: vec![] }
     }
 
+    // Width in columns, not bytes
     pub fn len(&self) -> usize {
-        format!("{}", self).len()
+        format!("{}", self).width()
     }
 
     pub fn count(&self) -> usize {
@@ -27,11 +54,57 @@ impl Binds {
         self.binds.is_empty()
     }
 
-    pub fn bind(mut self, input: Input, action: impl Into<String>) -> Self {
-        self.binds.push(input);
-        self.actions.push(action.into());
+    pub fn iter(&self) -> impl Iterator<Item = &Bind> {
+        self.binds.iter()
+    }
+
+    // Only shown, the input still goes to run
+    pub fn bind(self, input: Input, label: impl Into<String>) -> Self {
+        self.bind_sequence(input, label)
+    }
+
+    pub fn bind_sequence(
+        mut self,
+        sequence: impl Into<Sequence>,
+        label: impl Into<String>,
+    ) -> Self {
+        self.binds.push(Bind {
+            sequence: sequence.into(),
+            label: label.into(),
+            action: None,
+        });
         self
     }
+
+    // Shown and dispatched to the handler for the action's type
+    pub fn action(self, input: Input, label: impl Into<String>, action: impl Action) -> Self {
+        self.action_sequence(input, label, action)
+    }
+
+    pub fn action_sequence(
+        mut self,
+        sequence: impl Into<Sequence>,
+        label: impl Into<String>,
+        action: impl Action,
+    ) -> Self {
+        self.binds.push(Bind {
+            sequence: sequence.into(),
+            label: label.into(),
+            action: Some(Box::new(action)),
+        });
+        self
+    }
+
+    // Actions bound to the sequence, in the order they were added
+    pub fn actions_for<'a>(
+        &'a self,
+        sequence: &'a Sequence,
+    ) -> impl Iterator<Item = &'a dyn Action> {
+        self.binds
+            .iter()
+            .filter(move |b| b.sequence == *sequence)
+            .filter_map(|b| b.action.as_deref())
+    }
 }
 
 impl From<Vec<(Input, String)>> for Binds {
@@ -39,8 +112,7 @@ impl From<Vec<(Input, String)>> for Binds {
         let mut s = Self::new();
 
         for (key, a) in v {
-            s.binds.push(key);
-            s.actions.push(a);
+            s = s.bind(key, a);
         }
 
         s
@@ -52,8 +124,7 @@ impl<const N: usize> From<[(Input, String); N]> for Binds {
         let mut kb = Self::new();
 
         for (input, a) in binds {
-            kb.binds.push(input);
-            kb.actions.push(a);
+            kb = kb.bind(input, a);
         }
 
         kb
@@ -62,8 +133,8 @@ impl<const N: usize> From<[(Input, String); N]> for Binds {
 
 impl Display for Binds {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (key, action) in self.binds.iter().zip(self.actions.iter()) {
-            write!(f, "<{}> {}", key, action)?;
+        for bind in &self.binds {
+            write!(f, "<{}> {}", bind.sequence, bind.label)?;
         }
 
         Ok(())
@@ -72,9 +143,19 @@ impl Display for Binds {
 
 #[cfg(test)]
 mod tests {
-    use super::Binds;
+    use super::{Action, Binds};
+    use crate::input::Input;
     use crate::input::key::{Key, KeyState};
     use crate::input::modifier::Modifier;
+    use crate::make_action;
+
+    #[derive(Clone, PartialEq, Debug)]
+    enum Edit {
+        Undo,
+        Redo,
+    }
+
+    make_action!(Edit);
 
     #[test]
     fn new_is_empty() {
@@ -150,4 +231,65 @@ mod tests {
             .bind((Key::b, Modifier::Ctrl).into(), "B");
         assert_eq!(kb.len(), format!("{}", kb).len());
     }
+
+    #[test]
+    fn len_counts_columns() {
+        let kb = Binds::new().bind(Key::Char('é').into(), "Accent");
+        assert_eq!(format!("{}", kb), "<é> Accent");
+        assert_eq!(kb.len(), 10);
+
+        // Wide chars take two columns, combining ones none
+        let kb = Binds::new().bind(Key::a.into(), "漢字e\u{301}");
+        assert_eq!(kb.len(), 9);
+    }
+
+    #[test]
+    fn actions() {
+        let kb = Binds::new()
+            .bind(Key::q.into(), "Quit")
+            .action((Key::z, Modifier::Ctrl).into(), "Undo", Edit::Undo)
+            .action((Key::y, Modifier::Ctrl).into(), "Redo", Edit::Redo);
+
+        assert_eq!(format!("{}", kb), "<q> Quit<Ctrl + z> Undo<Ctrl + y> Redo");
+        assert_eq!(kb.actions_for(&Input::from(Key::q).into()).count(), 0);
+
+        let sequence = Input::from((Key::y, Modifier::Ctrl)).into();
+        let found: Vec<_> = kb.actions_for(&sequence).collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].as_any().downcast_ref::<Edit>(), Some(&Edit::Redo));
+        assert!(Action::eq(found[0], &Edit::Redo));
+        assert!(!Action::eq(found[0], &Edit::Undo));
+    }
+
+    #[test]
+    fn sequences() {
+        let kb = Binds::new()
+            .bind_sequence([Key::g.into(), Key::g.into()], "Top")
+            .action_sequence(
+                [
+                    (Key::x, Modifier::Ctrl).into(),
+                    (Key::s, Modifier::Ctrl).into(),
+                ],
+                "Save",
+                Edit::Redo,
+            );
+
+        assert_eq!(format!("{}", kb), "<g g> Top<Ctrl + x Ctrl + s> Save");
+        assert_eq!(kb.len(), format!("{}", kb).len());
+
+        let save = [
+            (Key::x, Modifier::Ctrl).into(),
+            (Key::s, Modifier::Ctrl).into(),
+        ]
+        .into();
+        assert_eq!(kb.actions_for(&save).count(), 1);
+    }
+
+    #[test]
+    fn action_equality_and_clone() {
+        let kb1 = Binds::new().action(Key::u.into(), "Undo", Edit::Undo);
+        let kb2 = Binds::new().action(Key::u.into(), "Undo", Edit::Redo);
+        assert_eq!(kb1, kb1.clone());
+        assert_ne!(kb1, kb2);
+    }
 }
@@ -1,5 +1,6 @@
+use super::SyntaxError;
 use crate::printf;
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Protocol {
@@ -7,11 +8,24 @@ pub enum Protocol {
     Kitty,
 }
 
+// Disambiguate escape codes, report event types, alternate keys, all keys as escape codes and
+// associated text
+const KITTY_FLAGS: u8 = 0b11111;
+
 impl Protocol {
+    // Flags are pushed onto the terminal's own stack so whatever was set before comes back
+    // with deactivate
     pub fn activate(&self) {
         match self {
-            Protocol::Default => printf!("\x1b[=0;1u"),
-            Protocol::Kitty => printf!("\x1b[={};1u", 0b1111),
+            Protocol::Default => {}
+            Protocol::Kitty => printf!("\x1b[>{}u", KITTY_FLAGS),
+        }
+    }
+
+    pub fn deactivate(&self) {
+        match self {
+            Protocol::Default => {}
+            Protocol::Kitty => printf!("\x1b[<u"),
         }
     }
 }
@@ -62,6 +76,18 @@ pub enum Key {
     PageUp,   // \x1b[5~
     PageDown, // \x1b[6~
 
+    // Only reported with the kitty protocol
+    CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    Menu,
+    Keypad(KeypadKey),
+    Media(MediaKey),
+    // A modifier pressed on its own
+    Modifier(ModifierKey),
+
     // Control characters
     Escape,    // '\x1b'
     Backspace, // '\x08' or '\x7f'
@@ -164,6 +190,259 @@ pub enum Key {
     Bar,        // '|'
     RBrace,     // '}'
     Tilde,      // '~'
+
+    // Any other printable character, like 'é' or '日'
+    Char(char),
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum KeypadKey {
+    D0,
+    D1,
+    D2,
+    D3,
+    D4,
+    D5,
+    D6,
+    D7,
+    D8,
+    D9,
+    Decimal,
+    Divide,
+    Multiply,
+    Subtract,
+    Add,
+    Enter,
+    Equal,
+    Separator,
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Insert,
+    Delete,
+    Begin,
+}
+
+impl KeypadKey {
+    const ALL: [KeypadKey; 29] = [
+        KeypadKey::D0,
+        KeypadKey::D1,
+        KeypadKey::D2,
+        KeypadKey::D3,
+        KeypadKey::D4,
+        KeypadKey::D5,
+        KeypadKey::D6,
+        KeypadKey::D7,
+        KeypadKey::D8,
+        KeypadKey::D9,
+        KeypadKey::Decimal,
+        KeypadKey::Divide,
+        KeypadKey::Multiply,
+        KeypadKey::Subtract,
+        KeypadKey::Add,
+        KeypadKey::Enter,
+        KeypadKey::Equal,
+        KeypadKey::Separator,
+        KeypadKey::Left,
+        KeypadKey::Right,
+        KeypadKey::Up,
+        KeypadKey::Down,
+        KeypadKey::PageUp,
+        KeypadKey::PageDown,
+        KeypadKey::Home,
+        KeypadKey::End,
+        KeypadKey::Insert,
+        KeypadKey::Delete,
+        KeypadKey::Begin,
+    ];
+}
+
+impl Display for KeypadKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            KeypadKey::D0 => "0",
+            KeypadKey::D1 => "1",
+            KeypadKey::D2 => "2",
+            KeypadKey::D3 => "3",
+            KeypadKey::D4 => "4",
+            KeypadKey::D5 => "5",
+            KeypadKey::D6 => "6",
+            KeypadKey::D7 => "7",
+            KeypadKey::D8 => "8",
+            KeypadKey::D9 => "9",
+            KeypadKey::Decimal => ".",
+            KeypadKey::Divide => "/",
+            KeypadKey::Multiply => "*",
+            KeypadKey::Subtract => "-",
+            KeypadKey::Add => "+",
+            KeypadKey::Enter => "Enter",
+            KeypadKey::Equal => "=",
+            KeypadKey::Separator => ",",
+            KeypadKey::Left => "←",
+            KeypadKey::Right => "→",
+            KeypadKey::Up => "↑",
+            KeypadKey::Down => "↓",
+            KeypadKey::PageUp => "PgUp",
+            KeypadKey::PageDown => "PgDn",
+            KeypadKey::Home => "Home",
+            KeypadKey::End => "End",
+            KeypadKey::Insert => "Ins",
+            KeypadKey::Delete => "Del",
+            KeypadKey::Begin => "Begin",
+        };
+
+        write!(f, "KP{}", name)
+    }
+}
+
+impl FromStr for KeypadKey {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name(s, &KeypadKey::ALL)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MediaKey {
+    Play,
+    Pause,
+    PlayPause,
+    Reverse,
+    Stop,
+    FastForward,
+    Rewind,
+    NextTrack,
+    PrevTrack,
+    Record,
+    VolumeDown,
+    VolumeUp,
+    Mute,
+}
+
+impl MediaKey {
+    const ALL: [MediaKey; 13] = [
+        MediaKey::Play,
+        MediaKey::Pause,
+        MediaKey::PlayPause,
+        MediaKey::Reverse,
+        MediaKey::Stop,
+        MediaKey::FastForward,
+        MediaKey::Rewind,
+        MediaKey::NextTrack,
+        MediaKey::PrevTrack,
+        MediaKey::Record,
+        MediaKey::VolumeDown,
+        MediaKey::VolumeUp,
+        MediaKey::Mute,
+    ];
+}
+
+impl Display for MediaKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MediaKey::Play => write!(f, "Play"),
+            MediaKey::Pause => write!(f, "MediaPause"),
+            MediaKey::PlayPause => write!(f, "PlayPause"),
+            MediaKey::Reverse => write!(f, "Reverse"),
+            MediaKey::Stop => write!(f, "Stop"),
+            MediaKey::FastForward => write!(f, "FastForward"),
+            MediaKey::Rewind => write!(f, "Rewind"),
+            MediaKey::NextTrack => write!(f, "NextTrack"),
+            MediaKey::PrevTrack => write!(f, "PrevTrack"),
+            MediaKey::Record => write!(f, "Record"),
+            MediaKey::VolumeDown => write!(f, "VolDown"),
+            MediaKey::VolumeUp => write!(f, "VolUp"),
+            MediaKey::Mute => write!(f, "Mute"),
+        }
+    }
+}
+
+impl FromStr for MediaKey {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name(s, &MediaKey::ALL)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ModifierKey {
+    LeftShift,
+    LeftCtrl,
+    LeftAlt,
+    LeftSuper,
+    LeftHyper,
+    LeftMeta,
+    RightShift,
+    RightCtrl,
+    RightAlt,
+    RightSuper,
+    RightHyper,
+    RightMeta,
+    IsoLevel3Shift,
+    IsoLevel5Shift,
+}
+
+impl ModifierKey {
+    const ALL: [ModifierKey; 14] = [
+        ModifierKey::LeftShift,
+        ModifierKey::LeftCtrl,
+        ModifierKey::LeftAlt,
+        ModifierKey::LeftSuper,
+        ModifierKey::LeftHyper,
+        ModifierKey::LeftMeta,
+        ModifierKey::RightShift,
+        ModifierKey::RightCtrl,
+        ModifierKey::RightAlt,
+        ModifierKey::RightSuper,
+        ModifierKey::RightHyper,
+        ModifierKey::RightMeta,
+        ModifierKey::IsoLevel3Shift,
+        ModifierKey::IsoLevel5Shift,
+    ];
+}
+
+impl Display for ModifierKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModifierKey::LeftShift => write!(f, "LShift"),
+            ModifierKey::LeftCtrl => write!(f, "LCtrl"),
+            ModifierKey::LeftAlt => write!(f, "LAlt"),
+            ModifierKey::LeftSuper => write!(f, "LSuper"),
+            ModifierKey::LeftHyper => write!(f, "LHyper"),
+            ModifierKey::LeftMeta => write!(f, "LMeta"),
+            ModifierKey::RightShift => write!(f, "RShift"),
+            ModifierKey::RightCtrl => write!(f, "RCtrl"),
+            ModifierKey::RightAlt => write!(f, "RAlt"),
+            ModifierKey::RightSuper => write!(f, "RSuper"),
+            ModifierKey::RightHyper => write!(f, "RHyper"),
+            ModifierKey::RightMeta => write!(f, "RMeta"),
+            ModifierKey::IsoLevel3Shift => write!(f, "Level3Shift"),
+            ModifierKey::IsoLevel5Shift => write!(f, "Level5Shift"),
+        }
+    }
+}
+
+impl FromStr for ModifierKey {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name(s, &ModifierKey::ALL)
+    }
+}
+
+// The value whose display name matches, ignoring case
+fn from_name<T: Display + Copy>(s: &str, all: &[T]) -> Result<T, SyntaxError> {
+    all.iter()
+        .find(|v| v.to_string().eq_ignore_ascii_case(s))
+        .copied()
+        .ok_or_else(|| SyntaxError(s.to_string()))
 }
 
 impl Key {
@@ -268,6 +547,7 @@ impl Key {
             '|' => Some(Key::Bar),
             '}' => Some(Key::RBrace),
             '~' => Some(Key::Tilde),
+            c if !c.is_ascii() && !c.is_control() => Some(Key::Char(c)),
             _ => None,
         }
     }
@@ -373,6 +653,7 @@ impl Key {
             Key::Bar => Some('|'),
             Key::RBrace => Some('}'),
             Key::Tilde => Some('~'),
+            Key::Char(c) => Some(*c),
             _ => None,
         }
     }
@@ -397,6 +678,15 @@ impl Display for Key {
             Key::Tab => write!(f, "Tab"),
             Key::Enter => write!(f, "Enter"),
             Key::Space => write!(f, "Space"),
+            Key::CapsLock => write!(f, "CapsLock"),
+            Key::ScrollLock => write!(f, "ScrollLock"),
+            Key::NumLock => write!(f, "NumLock"),
+            Key::PrintScreen => write!(f, "PrtSc"),
+            Key::Pause => write!(f, "Pause"),
+            Key::Menu => write!(f, "Menu"),
+            Key::Keypad(k) => write!(f, "{}", k),
+            Key::Media(k) => write!(f, "{}", k),
+            Key::Modifier(k) => write!(f, "{}", k),
             _ => {
                 if let Some(c) = self.to_char() {
                     write!(f, "{}", c)
@@ -408,9 +698,81 @@ impl Display for Key {
     }
 }
 
+// Accepts the names keys are displayed with in any case, plus a few spelled out aliases like
+// "escape" or "pagedown". A single character is always that character's key.
+impl FromStr for Key {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return match c {
+                '←' => Ok(Key::Left),
+                '↑' => Ok(Key::Up),
+                '→' => Ok(Key::Right),
+                '↓' => Ok(Key::Down),
+                _ => Key::from_char(c).ok_or_else(|| SyntaxError(s.to_string())),
+            };
+        }
+
+        let named = [
+            Key::Home,
+            Key::Insert,
+            Key::Delete,
+            Key::End,
+            Key::PageUp,
+            Key::PageDown,
+            Key::Escape,
+            Key::Backspace,
+            Key::Tab,
+            Key::Enter,
+            Key::Space,
+            Key::CapsLock,
+            Key::ScrollLock,
+            Key::NumLock,
+            Key::PrintScreen,
+            Key::Pause,
+            Key::Menu,
+        ];
+
+        if let Ok(key) = from_name(s, &named) {
+            return Ok(key);
+        }
+
+        let lower = s.to_ascii_lowercase();
+
+        match lower.as_str() {
+            "up" => return Ok(Key::Up),
+            "down" => return Ok(Key::Down),
+            "left" => return Ok(Key::Left),
+            "right" => return Ok(Key::Right),
+            "insert" => return Ok(Key::Insert),
+            "delete" => return Ok(Key::Delete),
+            "pageup" => return Ok(Key::PageUp),
+            "pagedown" => return Ok(Key::PageDown),
+            "escape" => return Ok(Key::Escape),
+            "return" => return Ok(Key::Enter),
+            "printscreen" => return Ok(Key::PrintScreen),
+            _ => {}
+        }
+
+        if let Some(n) = lower.strip_prefix('f')
+            && let Ok(n) = n.parse()
+        {
+            return Ok(Key::Function(n));
+        }
+
+        KeypadKey::from_str(s)
+            .map(Key::Keypad)
+            .or_else(|_| MediaKey::from_str(s).map(Key::Media))
+            .or_else(|_| ModifierKey::from_str(s).map(Key::Modifier))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{Key, KeyState};
+    use super::{Key, KeyState, KeypadKey, MediaKey, ModifierKey};
 
     #[test]
     fn from_char() {
@@ -456,6 +818,18 @@ mod test {
         assert_eq!(format!("{}", Key::Function(5)), "F5");
     }
 
+    #[test]
+    fn kitty_key_display() {
+        assert_eq!(format!("{}", Key::Keypad(KeypadKey::D5)), "KP5");
+        assert_eq!(format!("{}", Key::Keypad(KeypadKey::Enter)), "KPEnter");
+        assert_eq!(format!("{}", Key::Media(MediaKey::PlayPause)), "PlayPause");
+        assert_eq!(
+            format!("{}", Key::Modifier(ModifierKey::LeftShift)),
+            "LShift"
+        );
+        assert_eq!(format!("{}", Key::CapsLock), "CapsLock");
+    }
+
     #[test]
     fn arrow_key_display() {
         assert_eq!(format!("{}", Key::Up), "↑");
@@ -505,7 +879,69 @@ mod test {
 
     #[test]
     fn unhandled_char() {
-        // Non-ASCII / unmapped char should return None
-        assert_eq!(Key::from_char('é'), None);
+        // Unmapped control chars should return None
+        assert_eq!(Key::from_char('\0'), None);
+        assert_eq!(Key::from_char('\u{85}'), None);
+    }
+
+    #[test]
+    fn unicode_char() {
+        assert_eq!(Key::from_char('é'), Some(Key::Char('é')));
+        assert_eq!(Key::from_char('日'), Some(Key::Char('日')));
+        assert_eq!(Key::Char('🦀').to_char(), Some('🦀'));
+        assert_eq!(format!("{}", Key::Char('é')), "é");
+    }
+
+    #[test]
+    fn from_str_round_trip() {
+        let mut keys: Vec<Key> = (0u8..=127)
+            .filter_map(|c| Key::from_char(c as char))
+            .collect();
+
+        keys.extend([
+            Key::Function(1),
+            Key::Function(35),
+            Key::Up,
+            Key::Down,
+            Key::Left,
+            Key::Right,
+            Key::Home,
+            Key::Insert,
+            Key::Delete,
+            Key::End,
+            Key::PageUp,
+            Key::PageDown,
+            Key::CapsLock,
+            Key::ScrollLock,
+            Key::NumLock,
+            Key::PrintScreen,
+            Key::Pause,
+            Key::Menu,
+            Key::Char('é'),
+        ]);
+        keys.extend(KeypadKey::ALL.map(Key::Keypad));
+        keys.extend(MediaKey::ALL.map(Key::Media));
+        keys.extend(ModifierKey::ALL.map(Key::Modifier));
+
+        for key in keys {
+            assert_eq!(format!("{}", key).parse(), Ok(key), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn from_str_aliases() {
+        assert_eq!("esc".parse(), Ok(Key::Escape));
+        assert_eq!("ESCAPE".parse(), Ok(Key::Escape));
+        assert_eq!("return".parse(), Ok(Key::Enter));
+        assert_eq!("left".parse(), Ok(Key::Left));
+        assert_eq!("pagedown".parse(), Ok(Key::PageDown));
+        assert_eq!("f5".parse(), Ok(Key::Function(5)));
+        assert_eq!("kp+".parse(), Ok(Key::Keypad(KeypadKey::Add)));
+        assert_eq!("fastforward".parse(), Ok(Key::Media(MediaKey::FastForward)));
+        // Single characters keep their case
+        assert_eq!("F".parse(), Ok(Key::F));
+        assert_eq!("f".parse(), Ok(Key::f));
+        assert!("nope".parse::<Key>().is_err());
+        assert!("".parse::<Key>().is_err());
     }
 }
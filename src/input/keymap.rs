@@ -0,0 +1,258 @@
+use super::{
+    binds::{Action, Binds},
+    sequence::Sequence,
+};
+use std::{fmt::Display, fs, io, path::Path, str::FromStr};
+
+// Named actions and the inputs that trigger them, so users can rebind them from a file like
+//
+//     # Comments and blank lines are skipped
+//     quit = q
+//     quit = ctrl+c
+//     save = Ctrl + x Ctrl + s
+//     select = mouse:left
+//
+// Naming an action again adds another binding for it.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct Keymap {
+    entries: Vec<(String, Vec<Sequence>)>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Keymap { entries: vec![] }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Keymap, KeymapError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn bind(mut self, name: impl Into<String>, sequence: impl Into<Sequence>) -> Self {
+        let name = name.into();
+        let sequence = sequence.into();
+
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, sequences)) => sequences.push(sequence),
+            None => self.entries.push((name, vec![sequence])),
+        }
+
+        self
+    }
+
+    // Sequences bound to the action, in the order they were added
+    pub fn get(&self, name: &str) -> &[Sequence] {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, sequences)| &sequences[..])
+            .unwrap_or_default()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(n, _)| n.as_str())
+    }
+
+    // Actions bound in other replace their bindings here, the rest are kept. Meant for laying
+    // a user's keymap over the defaults.
+    pub fn overlay(mut self, other: Keymap) -> Self {
+        for (name, sequences) in other.entries {
+            match self.entries.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, s)) => *s = sequences,
+                None => self.entries.push((name, sequences)),
+            }
+        }
+
+        self
+    }
+
+    // Binds for each (name, label, action), one per sequence bound to the name. Names that
+    // aren't bound are left out.
+    pub fn binds<'a, A: Action + Clone>(
+        &self,
+        actions: impl IntoIterator<Item = (&'a str, &'a str, A)>,
+    ) -> Binds {
+        let mut binds = Binds::new();
+
+        for (name, label, action) in actions {
+            for sequence in self.get(name) {
+                binds = binds.action_sequence(sequence.clone(), label, action.clone());
+            }
+        }
+
+        binds
+    }
+}
+
+impl FromStr for Keymap {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keymap = Keymap::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            let error = |msg: String| KeymapError::Syntax(i + 1, msg);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, binding) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected name = binding".to_string()))?;
+
+            let name = name.trim();
+
+            if name.is_empty() {
+                return Err(error("missing action name".to_string()));
+            }
+
+            let sequence = binding
+                .parse::<Sequence>()
+                .map_err(|e| error(e.to_string()))?;
+
+            keymap = keymap.bind(name, sequence);
+        }
+
+        Ok(keymap)
+    }
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    Io(io::Error),
+    // Line number, starting at 1, and what was wrong with it
+    Syntax(usize, String),
+}
+
+impl From<io::Error> for KeymapError {
+    fn from(e: io::Error) -> Self {
+        KeymapError::Io(e)
+    }
+}
+
+impl Display for KeymapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeymapError::Io(e) => write!(f, "{}", e),
+            KeymapError::Syntax(line, msg) => write!(f, "line {}: {}", line, msg),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::input::{Input, key::Key, modifier::Modifier, mouse::Mouse};
+    use crate::make_action;
+
+    #[derive(Clone, PartialEq, Debug)]
+    enum Cmd {
+        Quit,
+        Save,
+    }
+
+    make_action!(Cmd);
+
+    const DEFAULTS: &str = "
+        # Defaults
+        quit = q
+        quit = ctrl+c
+
+        save = Ctrl + x Ctrl + s
+        select = mouse:left
+    ";
+
+    #[test]
+    fn parse() {
+        let keymap: Keymap = DEFAULTS.parse().unwrap();
+
+        assert_eq!(
+            keymap.names().collect::<Vec<_>>(),
+            ["quit", "save", "select"]
+        );
+        assert_eq!(
+            keymap.get("quit"),
+            [
+                Input::from(Key::q).into(),
+                Input::from((Key::c, Modifier::Ctrl)).into()
+            ]
+        );
+        assert_eq!(
+            keymap.get("save"),
+            [Sequence::from([
+                (Key::x, Modifier::Ctrl).into(),
+                (Key::s, Modifier::Ctrl).into(),
+            ])]
+        );
+        assert_eq!(keymap.get("select"), [Input::from(Mouse::Left).into()]);
+        assert!(keymap.get("open").is_empty());
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let Err(KeymapError::Syntax(line, _)) = "quit = q\n\nsave".parse::<Keymap>() else {
+            panic!();
+        };
+        assert_eq!(line, 3);
+
+        let Err(KeymapError::Syntax(line, msg)) = "quit = ctrl+nope".parse::<Keymap>() else {
+            panic!();
+        };
+        assert_eq!(line, 1);
+        assert!(msg.contains("nope"));
+
+        assert!(" = q".parse::<Keymap>().is_err());
+        assert!("quit =".parse::<Keymap>().is_err());
+    }
+
+    #[test]
+    fn overlay() {
+        let defaults: Keymap = DEFAULTS.parse().unwrap();
+        let keymap = defaults.overlay("quit = Esc\nopen = ctrl+o".parse().unwrap());
+
+        assert_eq!(keymap.get("quit"), [Input::from(Key::Escape).into()]);
+        assert_eq!(keymap.get("save").len(), 1);
+        assert_eq!(
+            keymap.get("open"),
+            [Input::from((Key::o, Modifier::Ctrl)).into()]
+        );
+    }
+
+    #[test]
+    fn binds() {
+        let keymap: Keymap = DEFAULTS.parse().unwrap();
+        let binds = keymap.binds([
+            ("quit", "Quit", Cmd::Quit),
+            ("save", "Save", Cmd::Save),
+            ("open", "Open", Cmd::Save),
+        ]);
+
+        assert_eq!(binds.count(), 3);
+        assert_eq!(
+            format!("{}", binds),
+            "<q> Quit<Ctrl + c> Quit<Ctrl + x Ctrl + s> Save"
+        );
+
+        let quit = Input::from((Key::c, Modifier::Ctrl)).into();
+        let found: Vec<_> = binds.actions_for(&quit).collect();
+        assert!(Action::eq(found[0], &Cmd::Quit));
+    }
+
+    #[test]
+    fn load() {
+        let path = std::env::temp_dir().join(format!("keymap-{}.conf", std::process::id()));
+
+        fs::write(&path, "quit = alt+enter\n").unwrap();
+        let keymap = Keymap::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            keymap.unwrap().get("quit"),
+            [Input::from((Key::Enter, Modifier::Alt)).into()]
+        );
+        assert!(matches!(Keymap::load(&path), Err(KeymapError::Io(_))));
+    }
+}
@@ -0,0 +1,199 @@
+use super::{
+    Input, ParseError,
+    key::{Key, KeypadKey, MediaKey, ModifierKey},
+    key_info, numbers,
+};
+
+// Kitty keyboard protocol
+// Sent as \x1b[key_code:shifted_key:base_layout_key;modifier_mask:event_type;textu
+// Every field but key_code can be left out or empty. buf is the whole sequence.
+pub(crate) fn parse(buf: &[u8], params: &str) -> Result<Input, ParseError> {
+    let malformed = || ParseError::Malformed(buf.to_vec());
+
+    let mut params = params.split(';');
+
+    let codes = numbers(params.next().unwrap_or_default()).ok_or_else(malformed)?;
+
+    let (mods, state) = key_info(params.next().unwrap_or_default()).ok_or_else(malformed)?;
+
+    // Associated text, only used when it is a single character
+    let text = {
+        let text = numbers(params.next().unwrap_or_default()).ok_or_else(malformed)?;
+
+        match text[..] {
+            [Some(c)] => char::from_u32(c).and_then(Key::from_char),
+            _ => None,
+        }
+    };
+
+    // The shifted key over the unshifted one if both were reported, the text only for codes
+    // that aren't a known key
+    let key = codes
+        .get(1)
+        .copied()
+        .flatten()
+        .and_then(key)
+        .or_else(|| codes.first().copied().flatten().and_then(key))
+        .or(text);
+
+    key.map(|key| Input::Key(key, state, mods))
+        .ok_or_else(|| ParseError::Unrecognized(buf.to_vec()))
+}
+
+// Key for a unicode codepoint, functional keys are encoded in the private use area
+pub(crate) fn key(code: u32) -> Option<Key> {
+    match code {
+        57358 => Some(Key::CapsLock),
+        57359 => Some(Key::ScrollLock),
+        57360 => Some(Key::NumLock),
+        57361 => Some(Key::PrintScreen),
+        57362 => Some(Key::Pause),
+        57363 => Some(Key::Menu),
+        // F13 to F35
+        c @ 57376..=57398 => Some(Key::Function((c - 57376 + 13) as u8)),
+        c @ 57399..=57427 => keypad(c).map(Key::Keypad),
+        c @ 57428..=57440 => media(c).map(Key::Media),
+        c @ 57441..=57454 => modifier(c).map(Key::Modifier),
+        0xe000..=0xf8ff => None,
+        _ => char::from_u32(code).and_then(Key::from_char),
+    }
+}
+
+fn keypad(code: u32) -> Option<KeypadKey> {
+    Some(match code {
+        57399 => KeypadKey::D0,
+        57400 => KeypadKey::D1,
+        57401 => KeypadKey::D2,
+        57402 => KeypadKey::D3,
+        57403 => KeypadKey::D4,
+        57404 => KeypadKey::D5,
+        57405 => KeypadKey::D6,
+        57406 => KeypadKey::D7,
+        57407 => KeypadKey::D8,
+        57408 => KeypadKey::D9,
+        57409 => KeypadKey::Decimal,
+        57410 => KeypadKey::Divide,
+        57411 => KeypadKey::Multiply,
+        57412 => KeypadKey::Subtract,
+        57413 => KeypadKey::Add,
+        57414 => KeypadKey::Enter,
+        57415 => KeypadKey::Equal,
+        57416 => KeypadKey::Separator,
+        57417 => KeypadKey::Left,
+        57418 => KeypadKey::Right,
+        57419 => KeypadKey::Up,
+        57420 => KeypadKey::Down,
+        57421 => KeypadKey::PageUp,
+        57422 => KeypadKey::PageDown,
+        57423 => KeypadKey::Home,
+        57424 => KeypadKey::End,
+        57425 => KeypadKey::Insert,
+        57426 => KeypadKey::Delete,
+        57427 => KeypadKey::Begin,
+        _ => return None,
+    })
+}
+
+fn media(code: u32) -> Option<MediaKey> {
+    Some(match code {
+        57428 => MediaKey::Play,
+        57429 => MediaKey::Pause,
+        57430 => MediaKey::PlayPause,
+        57431 => MediaKey::Reverse,
+        57432 => MediaKey::Stop,
+        57433 => MediaKey::FastForward,
+        57434 => MediaKey::Rewind,
+        57435 => MediaKey::NextTrack,
+        57436 => MediaKey::PrevTrack,
+        57437 => MediaKey::Record,
+        57438 => MediaKey::VolumeDown,
+        57439 => MediaKey::VolumeUp,
+        57440 => MediaKey::Mute,
+        _ => return None,
+    })
+}
+
+fn modifier(code: u32) -> Option<ModifierKey> {
+    Some(match code {
+        57441 => ModifierKey::LeftShift,
+        57442 => ModifierKey::LeftCtrl,
+        57443 => ModifierKey::LeftAlt,
+        57444 => ModifierKey::LeftSuper,
+        57445 => ModifierKey::LeftHyper,
+        57446 => ModifierKey::LeftMeta,
+        57447 => ModifierKey::RightShift,
+        57448 => ModifierKey::RightCtrl,
+        57449 => ModifierKey::RightAlt,
+        57450 => ModifierKey::RightSuper,
+        57451 => ModifierKey::RightHyper,
+        57452 => ModifierKey::RightMeta,
+        57453 => ModifierKey::IsoLevel3Shift,
+        57454 => ModifierKey::IsoLevel5Shift,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::input::{key::KeyState, modifier::Modifier, parse};
+
+    #[test]
+    fn functional_keys() {
+        assert!(parse(b"\x1b[57376u") == Ok(Key::Function(13).into()));
+        assert!(parse(b"\x1b[57398u") == Ok(Key::Function(35).into()));
+        assert!(parse(b"\x1b[57358u") == Ok(Key::CapsLock.into()));
+        assert!(parse(b"\x1b[57399u") == Ok(Key::Keypad(KeypadKey::D0).into()));
+        assert!(
+            parse(b"\x1b[57414;5u") == Ok((Key::Keypad(KeypadKey::Enter), Modifier::Ctrl).into())
+        );
+        assert!(parse(b"\x1b[57430u") == Ok(Key::Media(MediaKey::PlayPause).into()));
+        assert!(parse(b"\x1b[57364u").is_err());
+    }
+
+    #[test]
+    fn modifier_only_press() {
+        assert!(
+            parse(b"\x1b[57441;2:3u")
+                == Ok((
+                    Key::Modifier(ModifierKey::LeftShift),
+                    KeyState::Release,
+                    Modifier::Shift
+                )
+                    .into())
+        );
+    }
+
+    #[test]
+    fn lock_state() {
+        let Ok(Input::Key(key, _, mods)) = parse(b"\x1b[97;65u") else {
+            panic!();
+        };
+
+        assert_eq!(key, Key::a);
+        assert!(mods.caps_lock() && !mods.num_lock());
+        assert!(mods.is_empty());
+    }
+
+    #[test]
+    fn associated_text() {
+        assert!(parse(b"\x1b[97;;97u") == Ok(Key::a.into()));
+        assert!(parse(b"\x1b[97:65;2;65u") == Ok((Key::A, Modifier::Shift).into()));
+        // The key code wins over text that doesn't match it
+        assert!(parse(b"\x1b[113;1;97u") == Ok(Key::q.into()));
+        // Text is only used for codes that aren't a known key
+        assert!(parse(b"\x1b[57344;1;97u") == Ok(Key::a.into()));
+        // Keypad and functional keys keep their code
+        assert!(parse(b"\x1b[57399;;48u") == Ok(Key::Keypad(KeypadKey::D0).into()));
+        assert!(parse(b"\x1b[57413;;43u") == Ok(Key::Keypad(KeypadKey::Add).into()));
+        assert!(parse(b"\x1b[57376;;97u") == Ok(Key::Function(13).into()));
+        // Text of several characters falls back to the key code
+        assert!(parse(b"\x1b[97;1;97:98u") == Ok(Key::a.into()));
+    }
+
+    #[test]
+    fn empty_fields() {
+        assert!(parse(b"\x1b[97::98;5u") == Ok((Key::a, Modifier::Ctrl).into()));
+        assert!(parse(b"\x1b[97:x;5u").is_err());
+    }
+}
@@ -1,14 +1,19 @@
 pub mod binds;
 pub mod key;
+pub mod keymap;
+mod kitty;
 pub(crate) mod mask;
 pub mod modifier;
 pub mod mouse;
+pub mod sequence;
+pub mod tokenizer;
 
-use key::{Key, KeyState};
+use key::{Key, KeyState, KeypadKey};
 use mask::Mask;
 use modifier::{Modifier, ModifierList};
 use mouse::{Mouse, MouseState};
-use std::{fmt::Display, str};
+use std::{fmt::Display, str, str::FromStr};
+use tokenizer::{PASTE_END, PASTE_START};
 
 use crate::unit::Point;
 
@@ -16,6 +21,24 @@ use crate::unit::Point;
 pub enum Input {
     Key(Key, KeyState, ModifierList),
     Mouse(Mouse, MouseState, ModifierList, Point),
+    // Bracketed paste, the whole pasted text at once
+    Paste(String),
+    // Terminal window gained or lost focus
+    FocusGained,
+    FocusLost,
+    // Focus moved between widgets of the current scene, with the tags of the widgets it left
+    // and went to. None when there was no widget or it had no tag.
+    FocusChanged(Option<String>, Option<String>),
+    // Ctrl + C with AppOption::InterruptInput on
+    Interrupt,
+    // Ctrl + Z was pressed and the app is about to be stopped, and it was continued after.
+    // The terminal is back to how it was before the app while it's stopped.
+    Suspend,
+    Resume,
+    // Terminal was resized to (columns, rows), every scene has been laid out again by then
+    Resize(usize, usize),
+    // Raw bytes of a sequence that couldn't be parsed
+    Unknown(Vec<u8>),
 }
 
 impl From<Key> for Input {
@@ -47,8 +70,10 @@ impl From<Mouse> for Input {
         Input::Mouse(
             mouse,
             match mouse {
-                Mouse::Left | Mouse::Middle | Mouse::Right => MouseState::Click,
-                _ => MouseState::Scroll,
+                Mouse::WheelUp | Mouse::WheelDown | Mouse::WheelLeft | Mouse::WheelRight => {
+                    MouseState::Scroll
+                }
+                _ => MouseState::Click,
             },
             [].into(),
             ().into(),
@@ -67,8 +92,10 @@ impl<T: Into<ModifierList>> From<(Mouse, T)> for Input {
         Input::Mouse(
             mouse,
             match mouse {
-                Mouse::Left | Mouse::Middle | Mouse::Right => MouseState::Click,
-                _ => MouseState::Scroll,
+                Mouse::WheelUp | Mouse::WheelDown | Mouse::WheelLeft | Mouse::WheelRight => {
+                    MouseState::Scroll
+                }
+                _ => MouseState::Click,
             },
             mods.into(),
             ().into(),
@@ -82,13 +109,26 @@ impl<T: Into<ModifierList>> From<(Mouse, MouseState, T)> for Input {
     }
 }
 
+impl Input {
+    // Equality for binds, which are made without a position, so mouse inputs match wherever
+    // they happened
+    pub fn matches(&self, other: &Input) -> bool {
+        match (self, other) {
+            (Input::Mouse(b1, s1, m1, _), Input::Mouse(b2, s2, m2, _)) => {
+                b1 == b2 && s1 == s2 && m1 == m2
+            }
+            _ => self == other,
+        }
+    }
+}
+
 impl Display for Input {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Input::Key(key, state, mods) => {
-                if mods.0.is_empty()
+                if mods.is_empty()
                     || (*mods == ModifierList(vec![Modifier::Shift])
-                        && (' '..='~').contains(&key.to_char().unwrap_or('\0')))
+                        && key.to_char().is_some_and(|c| !c.is_control()))
                 {
                     write!(f, "{}{}", key, state)
                 } else {
@@ -96,12 +136,106 @@ impl Display for Input {
                 }
             }
             Input::Mouse(btn, state, mods, _) => {
-                if mods.0.is_empty() {
+                if mods.is_empty() {
                     write!(f, "{}{}", btn, state)
                 } else {
                     write!(f, "{} + {}{}", mods, btn, state)
                 }
             }
+            Input::Paste(text) => write!(f, "Paste ({} chars)", text.chars().count()),
+            Input::FocusGained => write!(f, "Focus Gained"),
+            Input::FocusLost => write!(f, "Focus Lost"),
+            Input::FocusChanged(from, to) => write!(
+                f,
+                "Focus Changed ({} → {})",
+                from.as_deref().unwrap_or("none"),
+                to.as_deref().unwrap_or("none")
+            ),
+            Input::Interrupt => write!(f, "Interrupt"),
+            Input::Suspend => write!(f, "Suspend"),
+            Input::Resume => write!(f, "Resume"),
+            Input::Resize(w, h) => write!(f, "Resize ({w} x {h})"),
+            Input::Unknown(bytes) => write!(f, "Unknown {bytes:x?}"),
+        }
+    }
+}
+
+// Inputs written the way they're displayed, like "Ctrl + a" or "Right Click (drag)", or as
+// shorthand like "ctrl+shift+a", "alt+enter" or "mouse:right". Pastes, focus changes, resizes,
+// suspends, unknown bytes and the position of mouse events can't be written.
+impl FromStr for Input {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || SyntaxError(s.to_string());
+        let text = s.trim();
+
+        if text.eq_ignore_ascii_case("Focus Gained") {
+            return Ok(Input::FocusGained);
+        } else if text.eq_ignore_ascii_case("Focus Lost") {
+            return Ok(Input::FocusLost);
+        } else if text.eq_ignore_ascii_case("Interrupt") {
+            return Ok(Input::Interrupt);
+        }
+
+        let (text, state) = match text.strip_suffix(')').and_then(|t| t.rsplit_once(" (")) {
+            Some((text, state)) => (text.trim_end(), Some(state.to_ascii_lowercase())),
+            None => (text, None),
+        };
+
+        // The key itself can be + (or KP+), so a trailing + is never a separator
+        let (mods, name) = match text.strip_suffix('+').unwrap_or(text).rfind('+') {
+            Some(i) => (&text[..i], text[i + 1..].trim()),
+            None => ("", text),
+        };
+
+        let mut list = ModifierList::default();
+
+        if !name.is_empty() && name.len() < text.len() {
+            for m in mods.split('+') {
+                list += m.trim().parse::<Modifier>().map_err(|_| err())?;
+            }
+        }
+
+        let mouse = |name: &str| -> Result<Input, SyntaxError> {
+            let btn: Mouse = name.parse().map_err(|_| err())?;
+
+            let state = match state.as_deref() {
+                None => match btn {
+                    Mouse::WheelUp | Mouse::WheelDown | Mouse::WheelLeft | Mouse::WheelRight => {
+                        MouseState::Scroll
+                    }
+                    _ => MouseState::Click,
+                },
+                Some("release") => MouseState::Release,
+                Some("drag") => MouseState::Drag,
+                Some("double") => MouseState::DoubleClick,
+                Some("triple") => MouseState::TripleClick,
+                Some("move") => MouseState::Move,
+                Some(_) => return Err(err()),
+            };
+
+            Ok(Input::Mouse(btn, state, list.clone(), ().into()))
+        };
+
+        if let Some(prefix) = name.get(..6)
+            && prefix.eq_ignore_ascii_case("mouse:")
+        {
+            return mouse(&name[6..]);
+        }
+
+        match name.parse::<Key>() {
+            Ok(key) => {
+                let state = match state.as_deref() {
+                    None => KeyState::Press,
+                    Some("repeat") => KeyState::Repeat,
+                    Some("release") => KeyState::Release,
+                    Some(_) => return Err(err()),
+                };
+
+                Ok(Input::Key(key, state, list))
+            }
+            Err(_) => mouse(name),
         }
     }
 }
@@ -112,177 +246,260 @@ macro_rules! single_byte_key_pat {
     };
 }
 
-// Parse into full input event
-// Panics represent where errors would be returned when implemented
-pub fn parse(buf: &[u8]) -> Option<Input> {
-    if buf.is_empty() {
-        None
-    } else {
-        match buf[0] {
-            // Keys with alt
-            0x1b if buf.len() == 2 => {
-                if let Some(Input::Key(key, KeyState::Press, mods)) = parse(&buf[1..]) {
-                    Some((key, mods + Modifier::Alt).into())
-                } else {
-                    None
-                }
+// Why a token couldn't be turned into an input, with the bytes it came from
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseError {
+    Empty,
+    // Well formed but not mapped to any input, like \x1b[30~
+    Unrecognized(Vec<u8>),
+    // Doesn't follow the format of its sequence, like non-numeric parameters
+    Malformed(Vec<u8>),
+    InvalidUtf8(Vec<u8>),
+}
+
+impl ParseError {
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            ParseError::Empty => &[],
+            ParseError::Unrecognized(b) | ParseError::Malformed(b) | ParseError::InvalidUtf8(b) => {
+                b
             }
+        }
+    }
 
-            // Key sent as escape sequence
-            0x1b if buf.len() > 2 => match buf[1] {
-                b'[' => match buf[buf.len() - 1] {
-                    // Functional Key
-                    b'~' if buf.len() > 3 => {
-                        let val = if buf[3].is_ascii_digit() {
-                            format!("{}{}", buf[2] - b'0', buf[3] - b'0')
-                        } else {
-                            format!("{}", buf[2] - b'0')
-                        };
-
-                        match val.parse::<u8>().unwrap_or_else(|_| panic!()) {
-                            1 => Some(Key::Home.into()),
-                            2 => Some(Key::Insert.into()),
-                            3 => Some(Key::Delete.into()),
-                            4 => Some(Key::End.into()),
-                            5 => Some(Key::PageUp.into()),
-                            6 => Some(Key::PageDown.into()),
-                            v @ 11..=15 => Some(Key::Function(v - 10).into()),
-                            v @ 17..=21 => Some(Key::Function(v - 11).into()),
-                            v @ 23..=24 => Some(Key::Function(v - 12).into()),
-                            _ => panic!("unknown tilde csi: {val:?} from {buf:x?}\n",),
-                        }
-                    }
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            ParseError::Empty => vec![],
+            ParseError::Unrecognized(b) | ParseError::Malformed(b) | ParseError::InvalidUtf8(b) => {
+                b
+            }
+        }
+    }
+}
 
-                    // Kitty keyboard protocol
-                    // Sent as \x1b[key_code:alt_key_code;modifier_mask:event_typeu
-                    b'u' => {
-                        let mut params = str::from_utf8(&buf[2..])
-                            .unwrap_or_else(|_| panic!())
-                            // Temporary solution to avoid crashing when multiple key presses are
-                            // sent at once. Eventually, will be handled separately and split
-                            // before passed.
-                            .split('u')
-                            .next()
-                            .unwrap_or_else(|| panic!())
-                            .split(';');
-
-                        let maybe_key = if let Some(code) = params.next() {
-                            if let Some((_, alt_code)) = code.split_once(':')
-                                && let Ok(key @ single_byte_key_pat!()) = alt_code.parse::<u8>()
-                            {
-                                Key::from_char(key as char)
-                            } else if let Ok(key @ single_byte_key_pat!()) = code.parse::<u8>() {
-                                Key::from_char(key as char)
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        };
-
-                        let (mods, state) = if let Some(key_info) = params.next() {
-                            let mut key_info = key_info.split(":");
-
-                            if let Some(mask) = key_info.next() {
-                                (
-                                    ModifierList::unmask(
-                                        mask.parse::<usize>().unwrap_or_else(|_| panic!()),
-                                    ),
-                                    if let Some(state) = key_info.next() {
-                                        KeyState::from(
-                                            state.parse::<u8>().unwrap_or_else(|_| panic!()),
-                                        )
-                                    } else {
-                                        KeyState::Press
-                                    },
-                                )
-                            } else {
-                                ([].into(), KeyState::Press)
-                            }
-                        } else {
-                            ([].into(), KeyState::Press)
-                        };
-
-                        maybe_key.map(|key| Input::Key(key, state, mods))
-                    }
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty input"),
+            ParseError::Unrecognized(b) => write!(f, "unrecognized input: {b:x?}"),
+            ParseError::Malformed(b) => write!(f, "malformed sequence: {b:x?}"),
+            ParseError::InvalidUtf8(b) => write!(f, "invalid utf-8: {b:x?}"),
+        }
+    }
+}
 
-                    // Mouse Input (digits format)
-                    // Sent as \x1b[<button;col;rowM or \x1b[<button;col;rowm
-                    b @ (b'M' | b'm') => {
-                        assert!(buf.starts_with(b"\x1b[<"));
-
-                        let params: Vec<usize> = str::from_utf8(&buf[3..])
-                            .unwrap_or_else(|_| panic!())
-                            // Same here, temporary solution to avoid crashing
-                            .split(b as char)
-                            .next()
-                            .unwrap_or_else(|| panic!())
-                            .split(';')
-                            .map(|n| n.parse::<usize>().unwrap_or_else(|_| panic!()))
-                            .collect();
-
-                        if params.len() == 3 {
-                            let (mask, col, row) = (params[0], params[1], params[2]);
-                            let (btn, mut state, mods) = Mouse::unmask(mask);
-
-                            if b == b'm' {
-                                state = MouseState::Release;
-                            }
-
-                            Some(Input::Mouse(btn, state, mods, (col, row).into()))
-                        } else {
-                            None
-                        }
-                    }
+impl std::error::Error for ParseError {}
 
-                    _ => match buf[2] {
-                        b'A' => Some(Key::Up.into()),
-                        b'B' => Some(Key::Down.into()),
-                        b'C' => Some(Key::Right.into()),
-                        b'D' => Some(Key::Left.into()),
-                        b'H' => Some(Key::Home.into()),
-                        b'F' => Some(Key::End.into()),
-                        b'P' => Some(Key::Function(1).into()),
-                        b'Q' => Some(Key::Function(2).into()),
-                        b'R' => Some(Key::Function(4).into()),
-                        // Focus Gain/Loss
-                        // b'I' => {}
-                        // b'O' => {}
-                        b'Z' => Some((Key::Tab, Modifier::Shift).into()),
-
-                        _ => None,
-                    },
-                },
-                b'O' => match buf[2] {
-                    b'A' => Some(Key::Up.into()),
-                    b'B' => Some(Key::Down.into()),
-                    b'C' => Some(Key::Right.into()),
-                    b'D' => Some(Key::Left.into()),
-                    b'H' => Some(Key::Home.into()),
-                    b'F' => Some(Key::End.into()),
-                    v @ b'P'..=b'S' => Some(Key::Function(v - b'O').into()),
-                    _ => panic!("could not parse escape sequence: {buf:x?}\n"),
-                },
-                _ => None,
-            },
+// Text that doesn't name an input, holding the part that couldn't be read
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SyntaxError(pub String);
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not an input: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
 
-            // Keys sent in a single character
-            c @ single_byte_key_pat!() => {
-                Some(Key::from_char(c as char).or_else(|| panic!())?.into())
+// Parse into full input event
+pub fn parse(buf: &[u8]) -> Result<Input, ParseError> {
+    let unrecognized = || ParseError::Unrecognized(buf.to_vec());
+
+    match buf {
+        [] => Err(ParseError::Empty),
+
+        // Bracketed paste, the end marker is missing if the paste was too long or too slow
+        _ if buf.starts_with(PASTE_START) => {
+            let text = &buf[PASTE_START.len()..];
+            let text = text.strip_suffix(PASTE_END).unwrap_or(text);
+
+            Ok(Input::Paste(String::from_utf8_lossy(text).into_owned()))
+        }
+
+        // Key sent as escape sequence
+        [0x1b, b'[', _, ..] => parse_csi(buf),
+        [0x1b, b'O', b] => match *b {
+            b'A' => Ok(Key::Up.into()),
+            b'B' => Ok(Key::Down.into()),
+            b'C' => Ok(Key::Right.into()),
+            b'D' => Ok(Key::Left.into()),
+            b'H' => Ok(Key::Home.into()),
+            b'F' => Ok(Key::End.into()),
+            v @ b'P'..=b'S' => Ok(Key::Function(v - b'O').into()),
+            _ => Err(unrecognized()),
+        },
+
+        // Keys with alt
+        [0x1b, rest @ ..] if !rest.is_empty() => match parse(rest) {
+            Ok(Input::Key(key, KeyState::Press, mods)) => Ok((key, mods + Modifier::Alt).into()),
+            Err(ParseError::InvalidUtf8(_)) => Err(ParseError::InvalidUtf8(buf.to_vec())),
+            _ => Err(unrecognized()),
+        },
+
+        // Keys sent in a single character
+        [c @ single_byte_key_pat!()] => Key::from_char(*c as char)
+            .map(Input::from)
+            .ok_or_else(unrecognized),
+
+        // Key with control
+        [c @ 0x01..=0x1A] => Key::from_char((c + 0x60) as char)
+            .map(|key| (key, Modifier::Ctrl).into())
+            .ok_or_else(unrecognized),
+
+        // Multi-byte UTF-8 character
+        _ => {
+            let s = str::from_utf8(buf).map_err(|_| ParseError::InvalidUtf8(buf.to_vec()))?;
+            let mut chars = s.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::from_char(c).map(Input::from).ok_or_else(unrecognized),
+                _ => Err(unrecognized()),
             }
+        }
+    }
+}
 
-            // Key with control
-            c @ 0x01..=0x1A => Some(
-                (
-                    Key::from_char((c + 0x60) as char).or_else(|| panic!())?,
-                    Modifier::Ctrl,
-                )
-                    .into(),
-            ),
+// \x1b[ params final
+fn parse_csi(buf: &[u8]) -> Result<Input, ParseError> {
+    let unrecognized = || ParseError::Unrecognized(buf.to_vec());
+    let malformed = || ParseError::Malformed(buf.to_vec());
+
+    let params = str::from_utf8(&buf[2..buf.len() - 1]).map_err(|_| malformed())?;
+
+    match buf[buf.len() - 1] {
+        // Functional Key
+        // Sent as \x1b[code~ or \x1b[code;modifier_mask:event_type~
+        b'~' => {
+            let mut params = params.split(';');
+            let code = params.next().unwrap_or_default();
+
+            let key = match code.parse::<u32>().map_err(|_| malformed())? {
+                1 | 7 => Key::Home,
+                2 => Key::Insert,
+                3 => Key::Delete,
+                4 | 8 => Key::End,
+                5 => Key::PageUp,
+                6 => Key::PageDown,
+                v @ 11..=15 => Key::Function(v as u8 - 10),
+                v @ 17..=21 => Key::Function(v as u8 - 11),
+                v @ 23..=26 => Key::Function(v as u8 - 12),
+                v @ 28..=29 => Key::Function(v as u8 - 13),
+                v @ 31..=34 => Key::Function(v as u8 - 14),
+                // Kitty sends some functional keys this way as well
+                v @ 0xe000..=0xf8ff => kitty::key(v).ok_or_else(unrecognized)?,
+                _ => return Err(unrecognized()),
+            };
+
+            with_modifiers(buf, key, [].into(), params.next())
+        }
+
+        // Kitty keyboard protocol
+        b'u' => kitty::parse(buf, params),
+
+        // Mouse Input (digits format)
+        // Sent as \x1b[<button;col;rowM or \x1b[<button;col;rowm
+        b @ (b'M' | b'm') => {
+            let params: Vec<usize> = params
+                .strip_prefix('<')
+                .ok_or_else(malformed)?
+                .split(';')
+                .map(|n| n.parse::<usize>().map_err(|_| malformed()))
+                .collect::<Result<_, _>>()?;
+
+            if params.len() == 3 {
+                let (mask, col, row) = (params[0], params[1], params[2]);
+                let (btn, mut state, mods) = Mouse::unmask(mask);
+
+                if b == b'm' {
+                    state = MouseState::Release;
+                }
 
-            _ => None,
+                Ok(Input::Mouse(btn, state, mods, (col, row).into()))
+            } else {
+                Err(malformed())
+            }
         }
+
+        // Focus Gain/Loss
+        b'I' if params.is_empty() => Ok(Input::FocusGained),
+        b'O' if params.is_empty() => Ok(Input::FocusLost),
+
+        // Cursor and navigation keys
+        // Sent as \x1b[X or \x1b[1;modifier_mask:event_typeX
+        b => {
+            let (key, mods) = match b {
+                b'A' => (Key::Up, [].into()),
+                b'B' => (Key::Down, [].into()),
+                b'C' => (Key::Right, [].into()),
+                b'D' => (Key::Left, [].into()),
+                b'E' => (Key::Keypad(KeypadKey::Begin), [].into()),
+                b'H' => (Key::Home, [].into()),
+                b'F' => (Key::End, [].into()),
+                b'P' => (Key::Function(1), [].into()),
+                b'Q' => (Key::Function(2), [].into()),
+                b'R' => (Key::Function(3), [].into()),
+                b'S' => (Key::Function(4), [].into()),
+                b'Z' => (Key::Tab, Modifier::Shift.into()),
+                _ => return Err(unrecognized()),
+            };
+
+            let mut params = params.split(';');
+
+            match params.next() {
+                None | Some("" | "1") => with_modifiers(buf, key, mods, params.next()),
+                Some(_) => Err(unrecognized()),
+            }
+        }
+    }
+}
+
+// Modifiers reported on top of the key's own, same as the kitty protocol
+fn with_modifiers(
+    buf: &[u8],
+    key: Key,
+    mut mods: ModifierList,
+    info: Option<&str>,
+) -> Result<Input, ParseError> {
+    let (extra, state) =
+        key_info(info.unwrap_or_default()).ok_or(ParseError::Malformed(buf.to_vec()))?;
+
+    for m in extra.0 {
+        mods += m;
+    }
+
+    Ok(Input::Key(key, state, mods))
+}
+
+// Modifier mask + 1 and an optional event type, like 5:2
+pub(crate) fn key_info(s: &str) -> Option<(ModifierList, KeyState)> {
+    let info = numbers(s)?;
+
+    let mask = info.first().copied().flatten().unwrap_or(1) as usize;
+    let state = info
+        .get(1)
+        .copied()
+        .flatten()
+        .map_or(KeyState::Press, |s| KeyState::from(s as usize));
+
+    Some((ModifierList::unmask(mask), state))
+}
+
+// Colon separated numbers, empty ones are None
+pub(crate) fn numbers(s: &str) -> Option<Vec<Option<u32>>> {
+    if s.is_empty() {
+        return Some(vec![]);
     }
+
+    s.split(':')
+        .map(|n| {
+            if n.is_empty() {
+                Some(None)
+            } else {
+                n.parse::<u32>().ok().map(Some)
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -355,51 +572,166 @@ mod test {
 
     #[test]
     fn parse_single_char() {
-        assert!(parse(b"\x1b") == Some(Key::Escape.into()));
-        assert!(parse(b"\n") == Some(Key::Enter.into()));
-        assert!(parse(b"a") == Some(Key::a.into()));
-        assert!(parse(b"\x01") == Some((Key::a, Modifier::Ctrl).into()));
-        assert!(parse(b"\x09") != Some((Key::i, Modifier::Ctrl).into()));
+        assert!(parse(b"\x1b") == Ok(Key::Escape.into()));
+        assert!(parse(b"\n") == Ok(Key::Enter.into()));
+        assert!(parse(b"a") == Ok(Key::a.into()));
+        assert!(parse(b"\x01") == Ok((Key::a, Modifier::Ctrl).into()));
+        assert!(parse(b"\x09") != Ok((Key::i, Modifier::Ctrl).into()));
     }
 
     #[test]
     fn parse_tidle() {
-        assert!(parse(b"\x1b[~").is_none());
-        assert!(parse(b"\x1b[1~") == Some(Key::Home.into()));
-        assert!(parse(b"\x1b[3~") == Some(Key::Delete.into()));
-        assert!(parse(b"\x1b[13~") == Some(Key::Function(3).into()));
-        assert!(parse(b"\x1b[24~") == Some(Key::Function(12).into()));
+        assert!(parse(b"\x1b[~").is_err());
+        assert!(parse(b"\x1b[1~") == Ok(Key::Home.into()));
+        assert!(parse(b"\x1b[3~") == Ok(Key::Delete.into()));
+        assert!(parse(b"\x1b[13~") == Ok(Key::Function(3).into()));
+        assert!(parse(b"\x1b[24~") == Ok(Key::Function(12).into()));
     }
 
     #[test]
-    #[should_panic]
     fn parse_invalid() {
-        parse(b"\x1b[30~");
+        assert_eq!(
+            parse(b"\x1b[30~"),
+            Err(ParseError::Unrecognized(b"\x1b[30~".to_vec()))
+        );
+        assert_eq!(parse(b""), Err(ParseError::Empty));
+        assert_eq!(parse(b"\x1bOx").unwrap_err().bytes(), b"\x1bOx");
+        assert_eq!(
+            parse(b"\xff"),
+            Err(ParseError::InvalidUtf8(b"\xff".to_vec()))
+        );
     }
 
     #[test]
-    fn parse_mods() {
-        assert!(parse(b"\x01") == Some((Key::a, [Modifier::Ctrl]).into()));
-        assert!(parse(b"\x1bc") == Some((Key::c, [Modifier::Alt]).into()));
-        assert!(parse(b"\x1b\x1b") == Some((Key::Escape, [Modifier::Alt]).into()));
+    fn parse_malformed() {
+        assert!(matches!(
+            parse(b"\x1b[9x;2u"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse(b"\x1b[97;zu"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(parse(b"\x1b[0;1M"), Err(ParseError::Malformed(_))));
+        assert!(matches!(
+            parse(b"\x1b[<0;1M"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse(b"\x1b[<0;a;1M"),
+            Err(ParseError::Malformed(_))
+        ));
     }
 
     #[test]
-    fn parse_kitty_protocol() {
-        assert!(parse(b"\x1b[97u") == Some(Key::a.into()));
+    fn parse_legacy_mods() {
+        assert!(parse(b"\x1b[1;5A") == Ok((Key::Up, Modifier::Ctrl).into()));
+        assert!(parse(b"\x1b[1;3D") == Ok((Key::Left, Modifier::Alt).into()));
+        assert!(parse(b"\x1b[1;2H") == Ok((Key::Home, Modifier::Shift).into()));
+        assert!(parse(b"\x1b[1;6F") == Ok((Key::End, [Modifier::Shift, Modifier::Ctrl]).into()));
+        assert!(parse(b"\x1b[1;5P") == Ok((Key::Function(1), Modifier::Ctrl).into()));
         assert!(
-            parse(b"\x1b[9;2:3u") == Some((Key::Tab, KeyState::Release, Modifier::Shift).into())
+            parse(b"\x1b[1;7S") == Ok((Key::Function(4), [Modifier::Alt, Modifier::Ctrl]).into())
+        );
+        assert!(parse(b"\x1b[5;5~") == Ok((Key::PageUp, Modifier::Ctrl).into()));
+        assert!(parse(b"\x1b[3;2~") == Ok((Key::Delete, Modifier::Shift).into()));
+        assert!(parse(b"\x1b[15;5~") == Ok((Key::Function(5), Modifier::Ctrl).into()));
+        assert!(
+            parse(b"\x1b[24;8~")
+                == Ok((
+                    Key::Function(12),
+                    [Modifier::Shift, Modifier::Alt, Modifier::Ctrl]
+                )
+                    .into())
+        );
+        assert!(parse(b"\x1b[1;4Z") == Ok((Key::Tab, [Modifier::Shift, Modifier::Alt]).into()));
+    }
+
+    #[test]
+    fn parse_legacy_event_types() {
+        assert!(parse(b"\x1b[1;1:3A") == Ok((Key::Up, KeyState::Release).into()));
+        assert!(
+            parse(b"\x1b[6;5:2~") == Ok((Key::PageDown, KeyState::Repeat, Modifier::Ctrl).into())
+        );
+        assert!(parse(b"\x1b[57427~") == Ok(Key::Keypad(KeypadKey::Begin).into()));
+        assert!(parse(b"\x1b[1;zA").is_err());
+        assert!(parse(b"\x1b[2;5A").is_err());
+    }
+
+    #[test]
+    fn parse_paste() {
+        assert!(
+            parse(b"\x1b[200~hello\nworld\x1b[201~") == Ok(Input::Paste("hello\nworld".into()))
+        );
+        assert!(parse("\x1b[200~日本\x1b[201~".as_bytes()) == Ok(Input::Paste("日本".into())));
+        assert!(parse(b"\x1b[200~\x1b[201~") == Ok(Input::Paste(String::new())));
+        assert!(parse(b"\x1b[200~cut off") == Ok(Input::Paste("cut off".into())));
+        assert_eq!(format!("{}", Input::Paste("abc".into())), "Paste (3 chars)");
+    }
+
+    #[test]
+    fn parse_focus() {
+        assert!(parse(b"\x1b[I") == Ok(Input::FocusGained));
+        assert!(parse(b"\x1b[O") == Ok(Input::FocusLost));
+        assert!(parse(b"\x1b[1;5I").is_err());
+    }
+
+    #[test]
+    fn parse_mods() {
+        assert!(parse(b"\x01") == Ok((Key::a, [Modifier::Ctrl]).into()));
+        assert!(parse(b"\x1bc") == Ok((Key::c, [Modifier::Alt]).into()));
+        assert!(parse(b"\x1b\x1b") == Ok((Key::Escape, [Modifier::Alt]).into()));
+    }
+
+    #[test]
+    fn parse_unicode() {
+        assert!(parse("é".as_bytes()) == Ok(Key::Char('é').into()));
+        assert!(parse("日".as_bytes()) == Ok(Key::Char('日').into()));
+        assert!(parse("🦀".as_bytes()) == Ok(Key::Char('🦀').into()));
+        assert!(parse("\x1bé".as_bytes()) == Ok((Key::Char('é'), Modifier::Alt).into()));
+        assert!(parse(b"\xc3").is_err());
+        assert!(parse("ab".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn parse_kitty_unicode() {
+        assert!(parse(b"\x1b[233u") == Ok(Key::Char('é').into()));
+        assert!(parse(b"\x1b[233:201;2u") == Ok((Key::Char('É'), Modifier::Shift).into()));
+        assert!(parse(b"\x1b[13u") == Ok(Key::Enter.into()));
+        assert!(parse(b"\x1b[57344u").is_err());
+    }
+
+    #[test]
+    fn display_unicode() {
+        assert_eq!(
+            format!("{}", Input::from((Key::Char('É'), Modifier::Shift))),
+            "É"
+        );
+        assert_eq!(
+            format!("{}", Input::from((Key::Char('é'), Modifier::Ctrl))),
+            "Ctrl + é"
         );
-        assert!(parse(b"\x1b[97:65;2u") == Some((Key::A, Modifier::Shift).into()));
-        assert!(parse(b"\x1b[97;5u") == Some((Key::a, Modifier::Ctrl).into()));
-        assert!(parse(b"\x1b[97;5:2u") == Some((Key::a, KeyState::Repeat, Modifier::Ctrl).into()));
+    }
+
+    #[test]
+    fn parse_kitty_protocol() {
+        assert!(parse(b"\x1b[97u") == Ok(Key::a.into()));
+        assert!(parse(b"\x1b[9;2:3u") == Ok((Key::Tab, KeyState::Release, Modifier::Shift).into()));
+        assert!(parse(b"\x1b[97:65;2u") == Ok((Key::A, Modifier::Shift).into()));
+        assert!(parse(b"\x1b[97;5u") == Ok((Key::a, Modifier::Ctrl).into()));
+        assert!(parse(b"\x1b[97;5:2u") == Ok((Key::a, KeyState::Repeat, Modifier::Ctrl).into()));
+        // Associated text
+        assert!(parse(b"\x1b[97;;97u") == Ok(Key::a.into()));
+        assert!(parse(b"\x1b[97:65;2;65u") == Ok((Key::A, Modifier::Shift).into()));
+        // Caps Lock is left to the lock state, the key is the one reported
+        assert!(parse(b"\x1b[97;65;65u") == Ok((Key::a, Modifier::CapsLock).into()));
     }
 
     #[test]
     fn parse_mouse_basic() {
         assert!(
             parse(b"\x1b[<0;10;20M")
-                == Some(Input::Mouse(
+                == Ok(Input::Mouse(
                     Mouse::Left,
                     MouseState::Click,
                     [].into(),
@@ -409,7 +741,7 @@ mod test {
 
         assert!(
             parse(b"\x1b[<0;10;20m")
-                == Some(Input::Mouse(
+                == Ok(Input::Mouse(
                     Mouse::Left,
                     MouseState::Release,
                     [].into(),
@@ -419,7 +751,7 @@ mod test {
 
         assert!(
             parse(b"\x1b[<42;5;6M")
-                == Some(Input::Mouse(
+                == Ok(Input::Mouse(
                     Mouse::Right,
                     MouseState::Drag,
                     Modifier::Alt.into(),
@@ -429,7 +761,7 @@ mod test {
 
         assert!(
             parse(b"\x1b[<64;30;12M")
-                == Some(Input::Mouse(
+                == Ok(Input::Mouse(
                     Mouse::WheelUp,
                     MouseState::Scroll,
                     [].into(),
@@ -438,11 +770,44 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_mouse_extended() {
+        assert!(
+            parse(b"\x1b[<35;7;9M")
+                == Ok(Input::Mouse(
+                    Mouse::None,
+                    MouseState::Move,
+                    [].into(),
+                    (7, 9).into()
+                ))
+        );
+
+        assert!(
+            parse(b"\x1b[<128;1;1M")
+                == Ok(Input::Mouse(
+                    Mouse::Back,
+                    MouseState::Click,
+                    [].into(),
+                    (1, 1).into()
+                ))
+        );
+
+        assert!(
+            parse(b"\x1b[<129;1;1m")
+                == Ok(Input::Mouse(
+                    Mouse::Forward,
+                    MouseState::Release,
+                    [].into(),
+                    (1, 1).into()
+                ))
+        );
+    }
+
     #[test]
     fn parse_mouse_modifiers() {
         assert!(
             parse(b"\x1b[<28;1;1M")
-                == Some(Input::Mouse(
+                == Ok(Input::Mouse(
                     Mouse::Left,
                     MouseState::Click,
                     [Modifier::Shift, Modifier::Alt, Modifier::Ctrl].into(),
@@ -452,7 +817,7 @@ mod test {
 
         assert!(
             parse(b"\x1b[<86;4;7M")
-                == Some(Input::Mouse(
+                == Ok(Input::Mouse(
                     Mouse::WheelLeft,
                     MouseState::Scroll,
                     [Modifier::Shift, Modifier::Ctrl].into(),
@@ -462,7 +827,7 @@ mod test {
 
         assert!(
             parse(b"\x1b[<4;2;3m")
-                == Some(Input::Mouse(
+                == Ok(Input::Mouse(
                     Mouse::Left,
                     MouseState::Release,
                     Modifier::Shift.into(),
@@ -470,4 +835,73 @@ mod test {
                 ))
         );
     }
+
+    #[test]
+    fn from_str_round_trip() {
+        let inputs: Vec<Input> = vec![
+            Key::a.into(),
+            Key::A.into(),
+            (Key::a, Modifier::Ctrl).into(),
+            (Key::a, [Modifier::Ctrl, Modifier::Shift]).into(),
+            (Key::Plus, Modifier::Ctrl).into(),
+            Key::Plus.into(),
+            (Key::Keypad(KeypadKey::Add), Modifier::Alt).into(),
+            (Key::Tab, Modifier::Shift).into(),
+            (Key::Enter, KeyState::Release, Modifier::Alt).into(),
+            (Key::Char('é'), KeyState::Repeat).into(),
+            (Key::Function(5), Modifier::Super).into(),
+            Key::LParen.into(),
+            (Key::RParen, KeyState::Release).into(),
+            Mouse::Left.into(),
+            Mouse::WheelUp.into(),
+            (Mouse::Right, MouseState::Drag, Modifier::Ctrl).into(),
+            (Mouse::None, MouseState::Move).into(),
+            (Mouse::Left, MouseState::DoubleClick).into(),
+            Input::FocusGained,
+            Input::FocusLost,
+            Input::Interrupt,
+        ];
+
+        for input in inputs {
+            assert_eq!(format!("{}", input).parse(), Ok(input.clone()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn from_str_shorthand() {
+        assert_eq!(
+            "ctrl+shift+a".parse(),
+            Ok(Input::from((Key::a, [Modifier::Ctrl, Modifier::Shift])))
+        );
+        assert_eq!(
+            "alt+enter".parse(),
+            Ok(Input::from((Key::Enter, Modifier::Alt)))
+        );
+        assert_eq!("f5".parse(), Ok(Input::from(Key::Function(5))));
+        assert_eq!(
+            "ctrl++".parse(),
+            Ok(Input::from((Key::Plus, Modifier::Ctrl)))
+        );
+        assert_eq!("mouse:right".parse(), Ok(Input::from(Mouse::Right)));
+        assert_eq!(
+            "ctrl + mouse:left (drag)".parse(),
+            Ok(Input::from((Mouse::Left, MouseState::Drag, Modifier::Ctrl)))
+        );
+        // Left on its own is the arrow key
+        assert_eq!("left".parse(), Ok(Input::from(Key::Left)));
+    }
+
+    #[test]
+    fn from_str_errors() {
+        assert!("".parse::<Input>().is_err());
+        assert!("ctrl+".parse::<Input>().is_err());
+        assert!("+ +".parse::<Input>().is_err());
+        assert!("hold+a".parse::<Input>().is_err());
+        assert!("a (drag)".parse::<Input>().is_err());
+        assert!("Left Click (repeat)".parse::<Input>().is_err());
+        assert_eq!(
+            "ctrl+nope".parse::<Input>(),
+            Err(SyntaxError("ctrl+nope".to_string()))
+        );
+    }
 }
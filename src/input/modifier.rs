@@ -1,5 +1,5 @@
-use super::mask::Mask;
-use std::{fmt::Display, ops};
+use super::{SyntaxError, mask::Mask};
+use std::{fmt::Display, ops, str::FromStr};
 
 // Unless kitty protocol is active:
 // - Shift will not be sent by itself, or only with Control (captured by terminal).
@@ -9,14 +9,24 @@ use std::{fmt::Display, ops};
 // yourself out
 // - Not all combinations with control are captured. This is unavoidable.
 // For example, Ctrl + H, I, or J return backspace, tab, enter respectively.
+// - Caps Lock and Num Lock are only reported by kitty protocol. They are lock state rather than
+// held keys, so they're ignored when comparing and displaying a ModifierList.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Modifier {
-    Shift = 0b00_0001,
-    Alt = 0b00_0010,
-    Ctrl = 0b00_0100,
-    Super = 0b00_1000,
-    Hyper = 0b01_0000,
-    Meta = 0b10_0000,
+    Shift = 0b0000_0001,
+    Alt = 0b0000_0010,
+    Ctrl = 0b0000_0100,
+    Super = 0b0000_1000,
+    Hyper = 0b0001_0000,
+    Meta = 0b0010_0000,
+    CapsLock = 0b0100_0000,
+    NumLock = 0b1000_0000,
+}
+
+impl Modifier {
+    pub fn is_lock(&self) -> bool {
+        matches!(self, Modifier::CapsLock | Modifier::NumLock)
+    }
 }
 
 impl ops::BitAnd for Modifier {
@@ -44,6 +54,26 @@ impl Display for Modifier {
             Modifier::Super => write!(f, "Super"),
             Modifier::Hyper => write!(f, "Hyper"),
             Modifier::Meta => write!(f, "Meta"),
+            Modifier::CapsLock => write!(f, "CapsLock"),
+            Modifier::NumLock => write!(f, "NumLock"),
+        }
+    }
+}
+
+impl FromStr for Modifier {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "shift" => Ok(Modifier::Shift),
+            "alt" => Ok(Modifier::Alt),
+            "ctrl" | "control" => Ok(Modifier::Ctrl),
+            "super" => Ok(Modifier::Super),
+            "hyper" => Ok(Modifier::Hyper),
+            "meta" => Ok(Modifier::Meta),
+            "capslock" => Ok(Modifier::CapsLock),
+            "numlock" => Ok(Modifier::NumLock),
+            _ => Err(SyntaxError(s.to_string())),
         }
     }
 }
@@ -51,6 +81,34 @@ impl Display for Modifier {
 #[derive(Clone, Default, Debug)]
 pub struct ModifierList(pub(crate) Vec<Modifier>);
 
+impl ModifierList {
+    // Modifiers being held, without lock state
+    pub fn held(&self) -> impl Iterator<Item = &Modifier> {
+        self.0.iter().filter(|m| !m.is_lock())
+    }
+
+    // No modifiers held
+    pub fn is_empty(&self) -> bool {
+        self.held().next().is_none()
+    }
+
+    pub fn caps_lock(&self) -> bool {
+        self.0.contains(&Modifier::CapsLock)
+    }
+
+    pub fn num_lock(&self) -> bool {
+        self.0.contains(&Modifier::NumLock)
+    }
+}
+
+// Same held modifiers in any order
+fn same_held(a: &[Modifier], b: &[Modifier]) -> bool {
+    let held = |m: &&Modifier| !m.is_lock();
+
+    a.iter().filter(held).count() == b.iter().filter(held).count()
+        && b.iter().filter(held).all(|m| a.contains(m))
+}
+
 impl Mask for ModifierList {
     type Output = Self;
 
@@ -92,6 +150,14 @@ impl Mask for ModifierList {
             mods += Modifier::Meta;
         }
 
+        if mask & (Modifier::CapsLock as usize) != 0 {
+            mods += Modifier::CapsLock;
+        }
+
+        if mask & (Modifier::NumLock as usize) != 0 {
+            mods += Modifier::NumLock;
+        }
+
         mods
     }
 }
@@ -117,17 +183,7 @@ impl ops::AddAssign<Modifier> for ModifierList {
 
 impl PartialEq for ModifierList {
     fn eq(&self, other: &Self) -> bool {
-        if self.0.len() != other.0.len() {
-            return false;
-        }
-
-        for m in &other.0 {
-            if !self.0.contains(m) {
-                return false;
-            }
-        }
-
-        true
+        same_held(&self.0, &other.0)
     }
 }
 
@@ -163,16 +219,7 @@ impl From<Vec<Modifier>> for ModifierList {
 
 impl PartialEq<Vec<Modifier>> for ModifierList {
     fn eq(&self, other: &Vec<Modifier>) -> bool {
-        if self.0.len() != other.len() {
-            return false;
-        }
-
-        for m in other {
-            if !self.0.contains(m) {
-                return false;
-            }
-        }
-        true
+        same_held(&self.0, other)
     }
 }
 
@@ -184,16 +231,7 @@ impl<const N: usize> From<[Modifier; N]> for ModifierList {
 
 impl<const N: usize> PartialEq<[Modifier; N]> for ModifierList {
     fn eq(&self, other: &[Modifier; N]) -> bool {
-        if self.0.len() != other.len() {
-            return false;
-        }
-
-        for m in other {
-            if !self.0.contains(m) {
-                return false;
-            }
-        }
-        true
+        same_held(&self.0, other)
     }
 }
 
@@ -202,8 +240,7 @@ impl Display for ModifierList {
         write!(
             f,
             "{}",
-            self.0
-                .iter()
+            self.held()
                 .map(|m| format!("{}", m))
                 .collect::<Vec<String>>()
                 .join(" + "),
@@ -256,7 +293,7 @@ mod test {
 
     #[test]
     fn unmask() {
-        assert!(ModifierList::unmask(0b1) == []);
+        assert!(ModifierList::unmask(0b1).is_empty());
         assert!(ModifierList::unmask(0b10) == [Modifier::Shift]);
         assert!(ModifierList::unmask(0b11) == [Modifier::Alt]);
         assert!(ModifierList::unmask(0b100) == [Modifier::Shift, Modifier::Alt]);
@@ -323,6 +360,22 @@ mod test {
         assert_eq!(a, c);
     }
 
+    #[test]
+    fn lock_state() {
+        // Ctrl with caps lock and num lock on
+        let mods = ModifierList::unmask(0b1100_0101);
+        assert!(mods.caps_lock());
+        assert!(mods.num_lock());
+        assert!(mods == Modifier::Ctrl);
+        assert_eq!(format!("{}", mods), "Ctrl");
+        assert_eq!(mods.mask(), 0b1100_0101);
+
+        let locked = ModifierList::unmask(0b0100_0001);
+        assert!(locked.caps_lock() && !locked.num_lock());
+        assert!(locked.is_empty());
+        assert!(locked == ModifierList::default());
+    }
+
     #[test]
     fn partial_eq_usize() {
         let mods = ModifierList(vec![Modifier::Ctrl, Modifier::Alt]);
@@ -331,4 +384,24 @@ mod test {
         assert!(mods == expected_mask);
         assert!(mods != (expected_mask + 1));
     }
+
+    #[test]
+    fn from_str() {
+        for m in [
+            Modifier::Shift,
+            Modifier::Alt,
+            Modifier::Ctrl,
+            Modifier::Super,
+            Modifier::Hyper,
+            Modifier::Meta,
+            Modifier::CapsLock,
+            Modifier::NumLock,
+        ] {
+            assert_eq!(format!("{}", m).parse(), Ok(m));
+        }
+
+        assert_eq!("control".parse(), Ok(Modifier::Ctrl));
+        assert_eq!("ALT".parse(), Ok(Modifier::Alt));
+        assert!("cmd".parse::<Modifier>().is_err());
+    }
 }
@@ -1,9 +1,51 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use super::{
+    SyntaxError,
     mask::Mask,
     modifier::{Modifier, ModifierList},
 };
+use crate::unit::Point;
+
+// Which mouse events the terminal reports, always in the SGR (1006) format
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MouseCapture {
+    None,
+    // Press and release
+    Button,
+    // Also motion while a button is held
+    Drag,
+    // Also motion without any button held
+    AnyMotion,
+}
+
+impl MouseCapture {
+    pub fn activate(&self) {
+        match self {
+            MouseCapture::None => return,
+            MouseCapture::Button => crate::printf!("\x1b[?1000h"),
+            MouseCapture::Drag => crate::printf!("\x1b[?1002h"),
+            MouseCapture::AnyMotion => crate::printf!("\x1b[?1003h"),
+        }
+
+        crate::printf!("\x1b[?1006h");
+    }
+
+    pub fn deactivate(&self) {
+        match self {
+            MouseCapture::None => return,
+            MouseCapture::Button => crate::printf!("\x1b[?1000l"),
+            MouseCapture::Drag => crate::printf!("\x1b[?1002l"),
+            MouseCapture::AnyMotion => crate::printf!("\x1b[?1003l"),
+        }
+
+        crate::printf!("\x1b[?1006l");
+    }
+}
 
 // Reported as ESC [ < btn ; col ; row M (m if release)
 // The upper two bits of btn are translated one byte up
@@ -13,9 +55,14 @@ use super::{
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum MouseState {
     Click,
+    // Sent after the Click that made it one
+    DoubleClick,
+    TripleClick,
     Scroll,
     Release,
     Drag,
+    // Motion without a button held, only with MouseCapture::AnyMotion
+    Move,
 }
 
 impl Display for MouseState {
@@ -23,7 +70,10 @@ impl Display for MouseState {
         match self {
             MouseState::Click | MouseState::Scroll => write!(f, ""),
             MouseState::Release => write!(f, " (release)"),
+            MouseState::DoubleClick => write!(f, " (double)"),
+            MouseState::TripleClick => write!(f, " (triple)"),
             MouseState::Drag => write!(f, " (drag)"),
+            MouseState::Move => write!(f, " (move)"),
         }
     }
 }
@@ -33,10 +83,17 @@ pub enum Mouse {
     Left = 0b0000_0000,
     Middle = 0b0000_0001,
     Right = 0b0000_0010,
+    // No button, reported with motion or anything unknown
+    None = 0b0000_0011,
     WheelUp = 0b0100_0000,
     WheelDown = 0b0100_0001,
     WheelLeft = 0b0100_0010,
     WheelRight = 0b0100_0011,
+    // Buttons 8 to 11
+    Back = 0b1000_0000,
+    Forward = 0b1000_0001,
+    Button10 = 0b1000_0010,
+    Button11 = 0b1000_0011,
 }
 
 impl From<usize> for Mouse {
@@ -49,7 +106,11 @@ impl From<usize> for Mouse {
             0b0100_0001 => Mouse::WheelDown,
             0b0100_0010 => Mouse::WheelLeft,
             0b0100_0011 => Mouse::WheelRight,
-            _ => Mouse::Left, // Fallback to left button on unknown value
+            0b1000_0000 => Mouse::Back,
+            0b1000_0001 => Mouse::Forward,
+            0b1000_0010 => Mouse::Button10,
+            0b1000_0011 => Mouse::Button11,
+            _ => Mouse::None,
         }
     }
 }
@@ -70,15 +131,15 @@ impl Mask for Mouse {
         // Remove modifier bits
         let btn = (mask & !(MOUSE_SHIFT | MOUSE_ALT | MOUSE_CTRL | MOUSE_DRAG)).into();
 
-        let state = {
-            if mask & MOUSE_DRAG != 0 {
-                MouseState::Drag
-            } else {
-                match btn {
-                    Mouse::Left | Mouse::Middle | Mouse::Right => MouseState::Click,
-                    _ => MouseState::Scroll,
-                }
+        let state = match (btn, mask & MOUSE_DRAG != 0) {
+            (Mouse::None, true) => MouseState::Move,
+            (_, true) => MouseState::Drag,
+            // Release in the legacy format
+            (Mouse::None, false) => MouseState::Release,
+            (Mouse::WheelUp | Mouse::WheelDown | Mouse::WheelLeft | Mouse::WheelRight, false) => {
+                MouseState::Scroll
             }
+            (_, false) => MouseState::Click,
         };
 
         let mut mods: ModifierList = [].into();
@@ -109,10 +170,80 @@ impl Display for Mouse {
             Mouse::WheelDown => write!(f, "Scroll Down"),
             Mouse::WheelLeft => write!(f, "Scroll Left"),
             Mouse::WheelRight => write!(f, "Scroll Right"),
+            Mouse::None => write!(f, "Mouse"),
+            Mouse::Back => write!(f, "Back Click"),
+            Mouse::Forward => write!(f, "Forward Click"),
+            Mouse::Button10 => write!(f, "Button10 Click"),
+            Mouse::Button11 => write!(f, "Button11 Click"),
         }
     }
 }
 
+// Accepts the displayed names in any case, with or without spaces or the trailing "Click", so
+// "Right Click" and "right" are the same button. Wheels can also be named "wheelup" and so on.
+impl FromStr for Mouse {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        match name.strip_suffix("click").unwrap_or(&name) {
+            "left" => Ok(Mouse::Left),
+            "middle" => Ok(Mouse::Middle),
+            "right" => Ok(Mouse::Right),
+            "mouse" | "none" => Ok(Mouse::None),
+            "scrollup" | "wheelup" => Ok(Mouse::WheelUp),
+            "scrolldown" | "wheeldown" => Ok(Mouse::WheelDown),
+            "scrollleft" | "wheelleft" => Ok(Mouse::WheelLeft),
+            "scrollright" | "wheelright" => Ok(Mouse::WheelRight),
+            "back" => Ok(Mouse::Back),
+            "forward" => Ok(Mouse::Forward),
+            "button10" => Ok(Mouse::Button10),
+            "button11" => Ok(Mouse::Button11),
+            _ => Err(SyntaxError(s.to_string())),
+        }
+    }
+}
+
+// Counts clicks of the same button at the same spot, each within the interval of the last
+#[derive(Default, Debug)]
+pub(crate) struct ClickCounter {
+    last: Option<(Mouse, Point, Instant)>,
+    count: usize,
+}
+
+impl ClickCounter {
+    pub(crate) fn new() -> ClickCounter {
+        ClickCounter {
+            last: None,
+            count: 0,
+        }
+    }
+
+    // Number of clicks in a row, starting over after a triple click
+    pub(crate) fn click(
+        &mut self,
+        btn: Mouse,
+        pos: Point,
+        at: Instant,
+        interval: Duration,
+    ) -> usize {
+        self.count = match self.last {
+            Some((b, p, t)) if b == btn && p == pos && at.duration_since(t) <= interval => {
+                self.count % 3 + 1
+            }
+            _ => 1,
+        };
+
+        self.last = Some((btn, pos, at));
+        self.count
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -191,6 +322,53 @@ mod test {
         );
     }
 
+    #[test]
+    fn extra_buttons_and_motion() {
+        assert_eq!(
+            Mouse::unmask(128),
+            (Mouse::Back, MouseState::Click, [].into())
+        );
+        assert_eq!(
+            Mouse::unmask(129 + 4),
+            (Mouse::Forward, MouseState::Click, Modifier::Shift.into())
+        );
+        assert_eq!(
+            Mouse::unmask(131),
+            (Mouse::Button11, MouseState::Click, [].into())
+        );
+        assert_eq!(
+            Mouse::unmask(35),
+            (Mouse::None, MouseState::Move, [].into())
+        );
+        assert_eq!(
+            Mouse::unmask(3),
+            (Mouse::None, MouseState::Release, [].into())
+        );
+        assert_eq!(Mouse::from(0b1100_0000), Mouse::None);
+    }
+
+    #[test]
+    fn click_counter() {
+        let mut clicks = ClickCounter::new();
+        let interval = Duration::from_millis(400);
+        let t = Instant::now();
+        let at = |ms| t + Duration::from_millis(ms);
+        let pos: Point = (3, 4).into();
+
+        assert_eq!(clicks.click(Mouse::Left, pos, at(0), interval), 1);
+        assert_eq!(clicks.click(Mouse::Left, pos, at(300), interval), 2);
+        assert_eq!(clicks.click(Mouse::Left, pos, at(600), interval), 3);
+        assert_eq!(clicks.click(Mouse::Left, pos, at(700), interval), 1);
+        // Too slow
+        assert_eq!(clicks.click(Mouse::Left, pos, at(1200), interval), 1);
+        // Other button or position
+        assert_eq!(clicks.click(Mouse::Right, pos, at(1300), interval), 1);
+        assert_eq!(
+            clicks.click(Mouse::Right, (3, 5).into(), at(1400), interval),
+            1
+        );
+    }
+
     #[test]
     fn display_mouse_variants() {
         assert_eq!(format!("{}", Mouse::Left), "Left Click");
@@ -228,4 +406,29 @@ mod test {
             crate::input::Input::Mouse(Mouse::WheelUp, MouseState::Scroll, [].into(), ().into());
         assert_eq!(format!("{}", scroll), "Scroll Up");
     }
+
+    #[test]
+    fn from_str() {
+        for btn in [
+            Mouse::Left,
+            Mouse::Middle,
+            Mouse::Right,
+            Mouse::None,
+            Mouse::WheelUp,
+            Mouse::WheelDown,
+            Mouse::WheelLeft,
+            Mouse::WheelRight,
+            Mouse::Back,
+            Mouse::Forward,
+            Mouse::Button10,
+            Mouse::Button11,
+        ] {
+            assert_eq!(format!("{}", btn).parse(), Ok(btn));
+        }
+
+        assert_eq!("right".parse(), Ok(Mouse::Right));
+        assert_eq!("wheelup".parse(), Ok(Mouse::WheelUp));
+        assert_eq!("scroll down".parse(), Ok(Mouse::WheelDown));
+        assert!("Up".parse::<Mouse>().is_err());
+    }
 }
@@ -0,0 +1,398 @@
+use super::{Input, SyntaxError};
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+// Inputs pressed one after the other, like g g or Ctrl + x Ctrl + s
+#[derive(Clone, PartialEq, Debug)]
+pub struct Sequence(pub Vec<Input>);
+
+impl Sequence {
+    pub fn inputs(&self) -> &[Input] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Whether inputs are this sequence, with Input::matches
+    pub fn matches(&self, inputs: &[Input]) -> bool {
+        self.0.len() == inputs.len() && self.0.iter().zip(inputs).all(|(a, b)| a.matches(b))
+    }
+
+    // Whether the sequence continues past prefix
+    pub fn extends(&self, prefix: &[Input]) -> bool {
+        self.0.len() > prefix.len() && self.0.iter().zip(prefix).all(|(a, b)| a.matches(b))
+    }
+}
+
+impl From<Input> for Sequence {
+    fn from(input: Input) -> Self {
+        Sequence(vec![input])
+    }
+}
+
+impl From<Vec<Input>> for Sequence {
+    fn from(inputs: Vec<Input>) -> Self {
+        Sequence(inputs)
+    }
+}
+
+impl<const N: usize> From<[Input; N]> for Sequence {
+    fn from(inputs: [Input; N]) -> Self {
+        Sequence(inputs.to_vec())
+    }
+}
+
+impl Display for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, input) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{}", input)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Inputs separated by spaces, as displayed. Names can have spaces of their own, like
+// "Ctrl + x" or "Left Click", so each input is the most words that still read as one.
+impl FromStr for Sequence {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let mut inputs = vec![];
+        let mut i = 0;
+
+        if words.is_empty() {
+            return Err(SyntaxError(s.to_string()));
+        }
+
+        while i < words.len() {
+            let (input, end) = (i + 1..=words.len())
+                .rev()
+                .find_map(|end| Some((words[i..end].join(" ").parse().ok()?, end)))
+                .ok_or_else(|| SyntaxError(words[i].to_string()))?;
+
+            inputs.push(input);
+            i = end;
+        }
+
+        Ok(Sequence(inputs))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SequenceEvent {
+    Matched(Sequence),
+    // Not part of any sequence, handled like any other input
+    Unmatched(Input),
+}
+
+// Tracks inputs that could still become one of the candidate sequences. When a sequence is
+// also the start of a longer one, the matcher waits for the timeout before picking it.
+#[derive(Default, Debug)]
+pub struct SequenceMatcher {
+    pending: Vec<Input>,
+    // Key releases and repeats that came while inputs were pending, given out after them
+    held: Vec<Input>,
+    last: Option<Instant>,
+}
+
+impl SequenceMatcher {
+    pub fn new() -> SequenceMatcher {
+        SequenceMatcher {
+            pending: vec![],
+            held: vec![],
+            last: None,
+        }
+    }
+
+    // Inputs matched so far, for showing what's been typed
+    pub fn pending(&self) -> &[Input] {
+        &self.pending
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // When the pending inputs expire if nothing else comes
+    pub fn deadline(&self, timeout: Duration) -> Option<Instant> {
+        self.last
+            .filter(|_| self.is_pending())
+            .map(|last| last + timeout)
+    }
+
+    pub fn feed(
+        &mut self,
+        input: Input,
+        candidates: &[&Sequence],
+        now: Instant,
+        timeout: Duration,
+    ) -> Vec<SequenceEvent> {
+        let mut events = self.expire(candidates, now, timeout);
+
+        self.pending.push(input);
+        self.last = Some(now);
+
+        loop {
+            if candidates.iter().any(|c| c.extends(&self.pending)) {
+                break;
+            }
+
+            if candidates.iter().any(|c| c.matches(&self.pending)) {
+                events.push(SequenceEvent::Matched(Sequence(self.take())));
+                break;
+            }
+
+            // Whatever came before this input goes out on its own, then it starts over
+            let input = self.pending.pop().unwrap();
+
+            if self.pending.is_empty() {
+                events.push(SequenceEvent::Unmatched(input));
+                break;
+            }
+
+            events.extend(self.flush(candidates));
+            self.pending.push(input);
+        }
+
+        self.release_held(&mut events);
+        events
+    }
+
+    // Key releases and repeats can't be part of a sequence, but with the kitty protocol they
+    // come in between its presses. They wait until the pending inputs are given out, so none
+    // goes out before the press it belongs to.
+    pub fn hold(&mut self, input: Input) -> Vec<SequenceEvent> {
+        if self.is_pending() {
+            self.held.push(input);
+            vec![]
+        } else {
+            vec![SequenceEvent::Unmatched(input)]
+        }
+    }
+
+    // Give up on the pending inputs once nothing followed them in time
+    pub fn expire(
+        &mut self,
+        candidates: &[&Sequence],
+        now: Instant,
+        timeout: Duration,
+    ) -> Vec<SequenceEvent> {
+        match self.last {
+            Some(last) if self.is_pending() && now.duration_since(last) >= timeout => {
+                self.flush(candidates)
+            }
+            _ => vec![],
+        }
+    }
+
+    // The pending inputs as a sequence if they are one, otherwise one by one
+    pub fn flush(&mut self, candidates: &[&Sequence]) -> Vec<SequenceEvent> {
+        let pending = self.take();
+
+        let mut events = if pending.is_empty() {
+            vec![]
+        } else if candidates.iter().any(|c| c.matches(&pending)) {
+            vec![SequenceEvent::Matched(Sequence(pending))]
+        } else {
+            pending.into_iter().map(SequenceEvent::Unmatched).collect()
+        };
+
+        self.release_held(&mut events);
+        events
+    }
+
+    fn release_held(&mut self, events: &mut Vec<SequenceEvent>) {
+        if !self.is_pending() {
+            events.extend(self.held.drain(..).map(SequenceEvent::Unmatched));
+        }
+    }
+
+    fn take(&mut self) -> Vec<Input> {
+        self.last = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::input::{
+        key::{Key, KeyState},
+        modifier::Modifier,
+    };
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    fn candidates() -> Vec<Sequence> {
+        vec![
+            [Key::g.into(), Key::g.into()].into(),
+            [
+                (Key::x, Modifier::Ctrl).into(),
+                (Key::s, Modifier::Ctrl).into(),
+            ]
+            .into(),
+            Input::from(Key::d).into(),
+            [Key::d.into(), Key::d.into()].into(),
+        ]
+    }
+
+    fn seq<const N: usize>(keys: [Key; N]) -> SequenceEvent {
+        SequenceEvent::Matched(Sequence(keys.iter().map(|&key| key.into()).collect()))
+    }
+
+    #[test]
+    fn display() {
+        let s: Sequence = [(Key::x, Modifier::Ctrl).into(), Key::s.into()].into();
+        assert_eq!(format!("{}", s), "Ctrl + x s");
+    }
+
+    #[test]
+    fn two_key_sequence() {
+        let mut m = SequenceMatcher::new();
+        let c = candidates();
+        let c: Vec<&Sequence> = c.iter().collect();
+        let now = Instant::now();
+
+        assert!(m.feed(Key::g.into(), &c, now, TIMEOUT).is_empty());
+        assert_eq!(m.pending(), [Input::from(Key::g)]);
+        assert_eq!(
+            m.feed(Key::g.into(), &c, now, TIMEOUT),
+            [seq([Key::g, Key::g])]
+        );
+        assert!(!m.is_pending());
+    }
+
+    #[test]
+    fn unrelated_inputs_pass_through() {
+        let mut m = SequenceMatcher::new();
+        let c = candidates();
+        let c: Vec<&Sequence> = c.iter().collect();
+        let now = Instant::now();
+
+        assert_eq!(
+            m.feed(Key::a.into(), &c, now, TIMEOUT),
+            [SequenceEvent::Unmatched(Key::a.into())]
+        );
+
+        // Broken sequence gives back the prefix, then the new input is matched by itself
+        m.feed(Key::g.into(), &c, now, TIMEOUT);
+        assert_eq!(
+            m.feed(Key::d.into(), &c, now, TIMEOUT),
+            [SequenceEvent::Unmatched(Key::g.into())]
+        );
+        assert_eq!(m.pending(), [Input::from(Key::d)]);
+    }
+
+    #[test]
+    fn ambiguous_waits_for_timeout() {
+        let mut m = SequenceMatcher::new();
+        let c = candidates();
+        let c: Vec<&Sequence> = c.iter().collect();
+        let now = Instant::now();
+
+        assert_eq!(m.deadline(TIMEOUT), None);
+        assert!(m.feed(Key::d.into(), &c, now, TIMEOUT).is_empty());
+        assert_eq!(m.deadline(TIMEOUT), Some(now + TIMEOUT));
+        assert!(m.expire(&c, now + TIMEOUT / 2, TIMEOUT).is_empty());
+        assert_eq!(m.expire(&c, now + TIMEOUT, TIMEOUT), [seq([Key::d])]);
+        assert_eq!(m.deadline(TIMEOUT), None);
+
+        m.feed(Key::d.into(), &c, now, TIMEOUT);
+        assert_eq!(
+            m.feed(Key::d.into(), &c, now, TIMEOUT),
+            [seq([Key::d, Key::d])]
+        );
+
+        // Something else ends the wait early
+        m.feed(Key::d.into(), &c, now, TIMEOUT);
+        assert_eq!(
+            m.feed(Key::a.into(), &c, now, TIMEOUT),
+            [seq([Key::d]), SequenceEvent::Unmatched(Key::a.into())]
+        );
+    }
+
+    #[test]
+    fn timed_out_prefix_before_next_input() {
+        let mut m = SequenceMatcher::new();
+        let c = candidates();
+        let c: Vec<&Sequence> = c.iter().collect();
+        let now = Instant::now();
+
+        m.feed(Key::g.into(), &c, now, TIMEOUT);
+        assert_eq!(
+            m.feed(Key::g.into(), &c, now + TIMEOUT * 2, TIMEOUT),
+            [SequenceEvent::Unmatched(Key::g.into())]
+        );
+        assert!(m.is_pending());
+    }
+
+    #[test]
+    fn releases_wait_for_pending_presses() {
+        let mut m = SequenceMatcher::new();
+        let c = candidates();
+        let c: Vec<&Sequence> = c.iter().collect();
+        let now = Instant::now();
+        let release = Input::from((Key::g, KeyState::Release));
+
+        assert_eq!(
+            m.hold(release.clone()),
+            [SequenceEvent::Unmatched(release.clone())]
+        );
+
+        m.feed(Key::g.into(), &c, now, TIMEOUT);
+        assert!(m.hold(release.clone()).is_empty());
+        assert_eq!(
+            m.feed(Key::g.into(), &c, now, TIMEOUT),
+            [seq([Key::g, Key::g]), SequenceEvent::Unmatched(release)]
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        let sequences: Vec<Sequence> = vec![
+            [Key::g.into(), Key::g.into()].into(),
+            [
+                (Key::x, Modifier::Ctrl).into(),
+                (Key::s, Modifier::Ctrl).into(),
+            ]
+            .into(),
+            [(Key::Plus, Modifier::Ctrl).into(), Key::a.into()].into(),
+            [Key::Plus.into(), Key::Plus.into()].into(),
+            [
+                Key::Space.into(),
+                (crate::input::mouse::Mouse::Left, Modifier::Alt).into(),
+                Key::q.into(),
+            ]
+            .into(),
+        ];
+
+        for s in sequences {
+            assert_eq!(format!("{}", s).parse(), Ok(s.clone()), "{}", s);
+        }
+
+        assert_eq!(
+            "ctrl+x ctrl+s".parse(),
+            Ok(Sequence::from([
+                (Key::x, Modifier::Ctrl).into(),
+                (Key::s, Modifier::Ctrl).into(),
+            ]))
+        );
+        assert!("".parse::<Sequence>().is_err());
+        assert!("g nope".parse::<Sequence>().is_err());
+    }
+}
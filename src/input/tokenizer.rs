@@ -0,0 +1,345 @@
+// Splits raw terminal input into whole tokens for parse. Reads can end in the middle of a
+// sequence or contain several at once, so incomplete bytes are kept until the next feed.
+//
+// A token is one of:
+// - CSI: ESC [ params... final (0x40..=0x7e)
+// - SS3: ESC O final
+// - OSC/DCS/APC strings: ESC ] / ESC P / ESC _ ... terminated by BEL or ESC \
+// - ESC followed by a single character (alt + key)
+// - A single UTF-8 encoded character or control byte
+// - Bracketed paste: ESC [ 200 ~ ... ESC [ 201 ~, everything in between is one token
+
+pub(crate) const PASTE_START: &[u8] = b"\x1b[200~";
+pub(crate) const PASTE_END: &[u8] = b"\x1b[201~";
+
+// Longest sequence kept around while waiting for the rest of it
+const MAX_PENDING: usize = 4096;
+// Pastes can be a lot longer than any sequence
+const MAX_PASTE: usize = 1 << 24;
+
+#[derive(Default, Debug)]
+pub struct Tokenizer {
+    buf: Vec<u8>,
+}
+
+enum Scan {
+    // Token of the given length at the start of the buffer
+    Complete(usize),
+    // Need more bytes
+    Incomplete,
+}
+
+impl Tokenizer {
+    pub fn new() -> Tokenizer {
+        Tokenizer { buf: vec![] }
+    }
+
+    // Bytes of an unfinished token
+    pub fn pending(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    // Waiting on the end of a paste
+    pub fn in_paste(&self) -> bool {
+        self.buf.starts_with(PASTE_START)
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        self.buf.extend_from_slice(bytes);
+
+        let mut tokens = vec![];
+        let mut start = 0;
+
+        while start < self.buf.len() {
+            match scan(&self.buf[start..]) {
+                Scan::Complete(n) => {
+                    tokens.push(self.buf[start..start + n].to_vec());
+                    start += n;
+                }
+                Scan::Incomplete if self.buf.len() - start > max_pending(&self.buf[start..]) => {
+                    // Never going to finish, hand it over as is
+                    tokens.push(self.buf[start..].to_vec());
+                    start = self.buf.len();
+                }
+                Scan::Incomplete => break,
+            }
+        }
+
+        self.buf.drain(..start);
+
+        tokens
+    }
+
+    // Give up waiting on the rest of a sequence, used once no more input arrives. A lone
+    // ESC is the escape key, anything else is split up so no bytes get lost.
+    pub fn flush(&mut self) -> Vec<Vec<u8>> {
+        let pending = std::mem::take(&mut self.buf);
+
+        if pending.is_empty() {
+            vec![]
+        } else if pending.starts_with(PASTE_START) {
+            // The end marker didn't come in time, the paste is handed over with what it has
+            vec![pending]
+        } else if pending.len() == 1 || pending[0] != 0x1b {
+            vec![pending]
+        } else {
+            // ESC on its own, then whatever followed it
+            let mut tokens = vec![vec![0x1b]];
+            tokens.extend(self.feed(&pending[1..]));
+            tokens.extend(self.flush());
+            tokens
+        }
+    }
+}
+
+fn max_pending(buf: &[u8]) -> usize {
+    if buf.starts_with(PASTE_START) {
+        MAX_PASTE
+    } else {
+        MAX_PENDING
+    }
+}
+
+fn scan(buf: &[u8]) -> Scan {
+    if buf.starts_with(PASTE_START) {
+        return scan_paste(buf);
+    }
+
+    match buf[0] {
+        0x1b => scan_escape(buf),
+        b => match utf8_len(b) {
+            Some(n) if buf.len() >= n => {
+                if buf[1..n].iter().all(|b| b & 0b1100_0000 == 0b1000_0000) {
+                    Scan::Complete(n)
+                } else {
+                    // Broken sequence, only the lead byte goes on its own
+                    Scan::Complete(1)
+                }
+            }
+            Some(_) => Scan::Incomplete,
+            None => Scan::Complete(1),
+        },
+    }
+}
+
+fn scan_escape(buf: &[u8]) -> Scan {
+    match buf.get(1) {
+        None => Scan::Incomplete,
+        Some(b'[') => scan_csi(buf),
+        Some(b'O') => {
+            if buf.len() >= 3 {
+                Scan::Complete(3)
+            } else {
+                Scan::Incomplete
+            }
+        }
+        Some(b']' | b'P' | b'_') => scan_string(buf),
+        // Alt + escape, a second ESC is never the start of a new sequence here
+        Some(0x1b) => Scan::Complete(2),
+        Some(b) => match utf8_len(*b) {
+            Some(n) if buf.len() > n => Scan::Complete(1 + n),
+            Some(_) => Scan::Incomplete,
+            None => Scan::Complete(2),
+        },
+    }
+}
+
+fn scan_csi(buf: &[u8]) -> Scan {
+    for (i, b) in buf.iter().enumerate().skip(2) {
+        match b {
+            // Parameters and intermediates
+            0x20..=0x3f => {}
+            0x40..=0x7e => return Scan::Complete(i + 1),
+            // Anything else can't be part of it, end the sequence before it
+            _ => return Scan::Complete(i),
+        }
+    }
+
+    Scan::Incomplete
+}
+
+fn scan_string(buf: &[u8]) -> Scan {
+    for i in 2..buf.len() {
+        if buf[i] == 0x07 {
+            return Scan::Complete(i + 1);
+        }
+
+        if buf[i] == 0x1b && buf.get(i + 1) == Some(&b'\\') {
+            return Scan::Complete(i + 2);
+        }
+    }
+
+    Scan::Incomplete
+}
+
+fn scan_paste(buf: &[u8]) -> Scan {
+    buf[PASTE_START.len()..]
+        .windows(PASTE_END.len())
+        .position(|w| w == PASTE_END)
+        .map_or(Scan::Incomplete, |i| {
+            Scan::Complete(PASTE_START.len() + i + PASTE_END.len())
+        })
+}
+
+// Length of a UTF-8 encoded char from its first byte, None for single bytes
+fn utf8_len(b: u8) -> Option<usize> {
+    match b {
+        0xc0..=0xdf => Some(2),
+        0xe0..=0xef => Some(3),
+        0xf0..=0xf7 => Some(4),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tokens(chunks: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut t = Tokenizer::new();
+        let mut out = vec![];
+
+        for chunk in chunks {
+            out.extend(t.feed(chunk));
+        }
+
+        out.extend(t.flush());
+        out
+    }
+
+    #[test]
+    fn plain_bytes() {
+        assert_eq!(tokens(&[b"abu"]), [b"a", b"b", b"u"]);
+    }
+
+    #[test]
+    fn batched_sequences() {
+        assert_eq!(
+            tokens(&[b"\x1b[97u\x1b[<0;10;20Mx\x1bOP\x1b[A"]),
+            [
+                b"\x1b[97u".to_vec(),
+                b"\x1b[<0;10;20M".to_vec(),
+                b"x".to_vec(),
+                b"\x1bOP".to_vec(),
+                b"\x1b[A".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn mouse_report_with_u_after() {
+        assert_eq!(
+            tokens(&[b"\x1b[<35;1;2mu"]),
+            [b"\x1b[<35;1;2m".to_vec(), b"u".to_vec()]
+        );
+    }
+
+    #[test]
+    fn sequence_split_across_reads() {
+        let mut t = Tokenizer::new();
+        assert!(t.feed(b"a\x1b[9").len() == 1);
+        assert!(t.is_pending());
+        assert_eq!(t.pending(), b"\x1b[9");
+        assert_eq!(t.feed(b"7;5u"), [b"\x1b[97;5u"]);
+        assert!(!t.is_pending());
+    }
+
+    #[test]
+    fn lone_escape_waits_for_flush() {
+        let mut t = Tokenizer::new();
+        assert!(t.feed(b"\x1b").is_empty());
+        assert_eq!(t.flush(), [b"\x1b"]);
+    }
+
+    #[test]
+    fn flush_unfinished_sequence() {
+        let mut t = Tokenizer::new();
+        assert!(t.feed(b"\x1b[1;").is_empty());
+        assert_eq!(
+            t.flush(),
+            [
+                b"\x1b".to_vec(),
+                b"[".to_vec(),
+                b"1".to_vec(),
+                b";".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn alt_keys() {
+        assert_eq!(
+            tokens(&[b"\x1bc\x1b\x1b"]),
+            [b"\x1bc".to_vec(), b"\x1b\x1b".to_vec()]
+        );
+        assert_eq!(tokens(&["\x1bé".as_bytes()]), ["\x1bé".as_bytes()]);
+    }
+
+    #[test]
+    fn utf8_split_across_reads() {
+        let e = "é".as_bytes();
+        let mut t = Tokenizer::new();
+        assert!(t.feed(&e[..1]).is_empty());
+        assert_eq!(t.feed(&e[1..]), [e]);
+
+        assert_eq!(
+            tokens(&["日本".as_bytes()]),
+            ["日".as_bytes(), "本".as_bytes()]
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_is_split() {
+        assert_eq!(tokens(&[b"\xc3a"]), [b"\xc3".to_vec(), b"a".to_vec()]);
+        assert_eq!(tokens(&[b"\xff"]), [b"\xff"]);
+    }
+
+    #[test]
+    fn paste_is_one_token() {
+        assert_eq!(
+            tokens(&[b"a\x1b[200~hi\x1b[Athere\x1b[201~b"]),
+            [
+                b"a".to_vec(),
+                b"\x1b[200~hi\x1b[Athere\x1b[201~".to_vec(),
+                b"b".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn paste_split_across_reads() {
+        let mut t = Tokenizer::new();
+        assert!(t.feed(b"\x1b[20").is_empty());
+        assert!(t.feed(b"0~some\x1b").is_empty());
+        assert!(t.feed(b" text\x1b[20").is_empty());
+        assert_eq!(t.feed(b"1~"), [b"\x1b[200~some\x1b text\x1b[201~"]);
+        assert!(!t.is_pending());
+    }
+
+    #[test]
+    fn unterminated_paste_is_flushed() {
+        let mut t = Tokenizer::new();
+        assert!(t.feed(b"\x1b[200~some\x1b[A").is_empty());
+        assert_eq!(t.flush(), [b"\x1b[200~some\x1b[A"]);
+        assert!(!t.is_pending());
+
+        // Back to tokenizing keys
+        assert_eq!(t.feed(b"a\x1b[A"), [b"a".to_vec(), b"\x1b[A".to_vec()]);
+    }
+
+    #[test]
+    fn osc_string() {
+        assert_eq!(
+            tokens(&[b"\x1b]11;rgb:0/0/0\x07a\x1bP1$r\x1b\\"]),
+            [
+                b"\x1b]11;rgb:0/0/0\x07".to_vec(),
+                b"a".to_vec(),
+                b"\x1bP1$r\x1b\\".to_vec()
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 // pub mod event;
 pub mod app;
+pub mod backend;
 pub mod draw;
 pub mod input;
 pub mod log;
@@ -7,6 +8,7 @@ pub mod macros;
 pub mod panel;
 pub mod scene;
 pub mod style;
+pub mod testing;
 pub mod theme;
 pub mod unit;
 pub mod widget;
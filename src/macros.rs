@@ -81,3 +81,25 @@ macro_rules! make_scene_key {
         }
     };
 }
+
+#[macro_export]
+macro_rules! make_action {
+    ($t:ty) => {
+        impl $crate::input::binds::Action for $t {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn eq(&self, other: &dyn $crate::input::binds::Action) -> bool {
+                other
+                    .as_any()
+                    .downcast_ref::<Self>()
+                    .map_or(false, |o| self == o)
+            }
+
+            fn aclone(&self) -> Box<dyn $crate::input::binds::Action> {
+                Box::new(self.clone())
+            }
+        }
+    };
+}
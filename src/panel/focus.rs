@@ -0,0 +1,359 @@
+use super::{Panel, frame::Frame, widget_at_path};
+use crate::{unit::Point, widget::Widget};
+
+// Focus is the selected flag of one focusable widget in the frame. Widgets are found by their
+// path of child indices from the frame down, so untagged widgets can hold focus too.
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// Visible focusable widgets under panel with their tab index, in tree order
+fn focusables(
+    panel: &mut dyn Panel,
+    path: &mut Vec<usize>,
+    out: &mut Vec<(Option<usize>, Vec<usize>)>,
+) {
+    let (_, children) = panel.split_mut();
+
+    for (i, child) in children.iter_mut().enumerate() {
+        if child.style().hide {
+            continue;
+        }
+
+        path.push(i);
+
+        if child.style().focusable {
+            out.push((child.style().tab_index, path.clone()));
+        }
+
+        if let Some(panel) = child.as_panel() {
+            focusables(panel, path, out);
+        }
+
+        path.pop();
+    }
+}
+
+// Same search as Panel::selected
+fn selected_path(panel: &mut dyn Panel, path: &mut Vec<usize>) -> bool {
+    let (_, children) = panel.split_mut();
+
+    for (i, child) in children.iter_mut().enumerate() {
+        path.push(i);
+
+        if let Some(panel) = child.as_panel()
+            && selected_path(panel, path)
+        {
+            return true;
+        }
+
+        if child.style().selected {
+            return true;
+        }
+
+        path.pop();
+    }
+
+    false
+}
+
+// Center of the widget's outline when it was last drawn
+fn center(widget: &dyn Widget) -> Option<(isize, isize)> {
+    let (x, y) = widget.style().rect?.center();
+
+    Some((x as isize, y as isize))
+}
+
+impl Frame {
+    // Paths of every widget that can take focus, in tab order
+    pub(crate) fn focus_order(&mut self) -> Vec<Vec<usize>> {
+        let mut found = vec![];
+        focusables(self, &mut vec![], &mut found);
+
+        // Stable, so widgets without a tab index keep their tree order
+        found.sort_by_key(|(index, _)| index.map_or((1, 0), |i| (0, i)));
+        found.into_iter().map(|(_, path)| path).collect()
+    }
+
+    pub(crate) fn focused_path(&mut self) -> Option<Vec<usize>> {
+        let mut path = vec![];
+        selected_path(self, &mut path).then_some(path)
+    }
+
+    pub(crate) fn widget_at_path(&mut self, path: &[usize]) -> Option<&mut Box<dyn Widget>> {
+        widget_at_path(self, path)
+    }
+
+    pub fn focused(&mut self) -> Option<&mut Box<dyn Widget>> {
+        let path = self.focused_path()?;
+        self.widget_at_path(&path)
+    }
+
+    // Moves focus to the focusable widget with the tag. Returns whether there was one.
+    pub fn focus(&mut self, tag: &str) -> bool {
+        let path = self
+            .focus_order()
+            .into_iter()
+            .find(|p| self.widget_at_path(p).is_some_and(|w| w.style().tag == tag));
+
+        match path {
+            Some(path) => {
+                self.set_focus(&path);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Next widget in tab order, wrapping around
+    pub fn focus_next(&mut self) -> bool {
+        self.cycle_focus(true)
+    }
+
+    pub fn focus_prev(&mut self) -> bool {
+        self.cycle_focus(false)
+    }
+
+    // Closest focusable widget in the direction, going by where widgets were last drawn
+    pub fn focus_toward(&mut self, direction: Direction) -> bool {
+        let Some(from) = self
+            .focused_path()
+            .and_then(|p| self.widget_at_path(&p).and_then(|w| center(w.as_ref())))
+        else {
+            return false;
+        };
+
+        let mut best: Option<(isize, Vec<usize>)> = None;
+
+        for path in self.focus_order() {
+            let Some(to) = self.widget_at_path(&path).and_then(|w| center(w.as_ref())) else {
+                continue;
+            };
+
+            let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+
+            // Distance along the direction, and how far off to the side
+            let (ahead, aside) = match direction {
+                Direction::Up => (-dy, dx),
+                Direction::Down => (dy, dx),
+                Direction::Left => (-dx, dy),
+                Direction::Right => (dx, dy),
+            };
+
+            if ahead <= 0 {
+                continue;
+            }
+
+            let score = ahead + 2 * aside.abs();
+
+            if best.as_ref().is_none_or(|(s, _)| score < *s) {
+                best = Some((score, path));
+            }
+        }
+
+        match best {
+            Some((_, path)) => {
+                self.set_focus(&path);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Gives focus to the first widget in tab order when nothing that can hold it has it, like
+    // after the focused widget was hidden. Frames without focusable widgets are left alone.
+    pub fn ensure_focus(&mut self) {
+        let order = self.focus_order();
+
+        if order.is_empty() {
+            return;
+        }
+
+        match self.focused_path() {
+            Some(path) if order.contains(&path) => {}
+            _ => self.set_focus(&order[0]),
+        }
+    }
+
+    fn cycle_focus(&mut self, forward: bool) -> bool {
+        let order = self.focus_order();
+
+        if order.is_empty() {
+            return false;
+        }
+
+        let next = match self
+            .focused_path()
+            .and_then(|p| order.iter().position(|o| *o == p))
+        {
+            Some(i) if forward => (i + 1) % order.len(),
+            Some(i) => (i + order.len() - 1) % order.len(),
+            None if forward => 0,
+            None => order.len() - 1,
+        };
+
+        self.set_focus(&order[next]);
+        true
+    }
+
+    // Focuses the widget under pos, or the closest container around it that can take focus
+    pub fn focus_at(&mut self, pos: Point) -> bool {
+        let Some(mut path) = self.path_at(pos) else {
+            return false;
+        };
+
+        let order = self.focus_order();
+
+        while !path.is_empty() {
+            if order.contains(&path) {
+                self.set_focus(&path);
+                return true;
+            }
+
+            path.pop();
+        }
+
+        false
+    }
+
+    // Exactly one widget is selected afterwards
+    fn set_focus(&mut self, path: &[usize]) {
+        self.style_all(|attr| {
+            attr.deselect();
+        });
+
+        if let Some(widget) = self.widget_at_path(path) {
+            widget.style_mut().select();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        testing::render_frame,
+        widget::{attr::Attr, container::Container, label::Label},
+    };
+
+    fn label(tag: &str) -> Box<Label> {
+        Label::new(
+            tag,
+            Attr::new().tag(tag).size(3usize, 3usize).focusable().wrap(),
+        )
+    }
+
+    // a b
+    // c d, with a plain label in between that can't take focus
+    fn grid() -> Frame {
+        let mut top = Container::new(Attr::new().horizontal().hide_border().wrap());
+        top.addm(vec![label("a"), label("b")]);
+
+        let mut bottom = Container::new(Attr::new().horizontal().hide_border().wrap());
+        bottom.addm(vec![label("c"), label("d")]);
+
+        let mut frame = Frame::new(None);
+        frame.addm(vec![
+            top,
+            Label::new("x", Attr::new().tag("x").size(3usize, 3usize).wrap()),
+            bottom,
+        ]);
+        frame
+    }
+
+    fn focused_tag(frame: &mut Frame) -> Option<String> {
+        frame.focused().map(|w| w.style().tag.clone())
+    }
+
+    #[test]
+    fn tab_order() {
+        let mut frame = grid();
+        assert_eq!(focused_tag(&mut frame), None);
+
+        frame.ensure_focus();
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("a"));
+
+        let mut seen = vec![];
+        for _ in 0..4 {
+            frame.focus_next();
+            seen.push(focused_tag(&mut frame).unwrap());
+        }
+        assert_eq!(seen, ["b", "c", "d", "a"]);
+
+        frame.focus_prev();
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn explicit_tab_index() {
+        let mut frame = grid();
+        frame.get_child("d").unwrap().style_mut().tab_index(0);
+        frame.get_child("b").unwrap().style_mut().tab_index(1);
+
+        frame.ensure_focus();
+        let mut seen = vec![focused_tag(&mut frame).unwrap()];
+        for _ in 0..3 {
+            frame.focus_next();
+            seen.push(focused_tag(&mut frame).unwrap());
+        }
+        assert_eq!(seen, ["d", "b", "a", "c"]);
+    }
+
+    #[test]
+    fn only_one_focused() {
+        let mut frame = grid();
+        frame.get_child("x").unwrap().style_mut().select();
+
+        assert!(frame.focus("c"));
+        assert!(!frame.focus("x"));
+        assert!(!frame.focus("missing"));
+
+        let selected: Vec<_> = ["a", "b", "c", "d", "x"]
+            .into_iter()
+            .filter(|t| frame.get_child(t).unwrap().style().is_selected())
+            .collect();
+        assert_eq!(selected, ["c"]);
+    }
+
+    #[test]
+    fn hidden_widget_loses_focus() {
+        let mut frame = grid();
+        frame.focus("b");
+        frame.get_child("b").unwrap().style_mut().hide = true;
+
+        frame.ensure_focus();
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("a"));
+
+        frame.focus_next();
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn spatial() {
+        let mut frame = grid();
+        render_frame(&mut frame, 20, 20);
+        frame.focus("a");
+
+        assert!(frame.focus_toward(Direction::Right));
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("b"));
+
+        // Skips over the label that can't take focus
+        assert!(frame.focus_toward(Direction::Down));
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("d"));
+
+        assert!(frame.focus_toward(Direction::Left));
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("c"));
+
+        assert!(!frame.focus_toward(Direction::Down));
+        assert!(!frame.focus_toward(Direction::Left));
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("c"));
+
+        assert!(frame.focus_toward(Direction::Up));
+        assert_eq!(focused_tag(&mut frame).as_deref(), Some("a"));
+    }
+}
@@ -1,11 +1,14 @@
-use super::Panel;
+use super::{Panel, reconcile::reconcile, selected_in, settle};
 use crate::{
-    app::get_tsz,
-    draw::draw_frame,
+    draw::{buffer::Buffer, draw_frame},
+    input::binds::Bind,
     panel_shared,
     style::align::{AlignX, AlignY},
     unit::{Point, Unit},
-    widget::{Widget, WidgetList, attr::Attr},
+    widget::{
+        Widget, WidgetList,
+        attr::{Attr, Dirty},
+    },
 };
 
 #[derive(Clone, PartialEq)]
@@ -22,28 +25,93 @@ impl Frame {
         }
     }
 
-    pub fn render(&mut self) {
-        draw_frame(&self.attr);
+    pub fn render(&mut self, buf: &mut Buffer) {
+        draw_frame(buf, &self.attr);
 
         let (inner_x, inner_y) = self.bounds();
+        let (w, h) = buf.size();
 
         let anchor = Point::new(
             match self.attr.alignx {
                 AlignX::Left => Unit::CoR(2),
-                AlignX::Center => Unit::CoR((get_tsz().0 / 2) - (inner_x.calc() / 2)),
-                AlignX::Right => Unit::CoR(get_tsz().0) - inner_x,
+                AlignX::Center => Unit::CoR((w / 2) - (inner_x.calc() / 2)),
+                AlignX::Right => Unit::CoR(w) - inner_x,
             },
             match self.attr.aligny {
                 AlignY::Top => Unit::CoR(2),
-                AlignY::Center => Unit::CoR((get_tsz().1 / 2) - (inner_y.calc() / 2)),
-                AlignY::Bottom => Unit::CoR(get_tsz().1) - inner_y,
+                AlignY::Center => Unit::CoR((h / 2) - (inner_y.calc() / 2)),
+                AlignY::Bottom => Unit::CoR(h) - inner_y,
             },
         );
 
-        self.render_children(anchor);
+        self.render_children(anchor, buf);
+
+        self.attr.dirty = Dirty::Clean;
+        self.attr.dirty_below = false;
+    }
+
+    // Whether anything changed since the last render. When the frame itself is dirty it has to
+    // be drawn from scratch.
+    pub(crate) fn settle(&mut self) -> bool {
+        self.attr.dirty_below = settle(self);
+        self.attr.is_dirty() || self.attr.dirty_below
+    }
+
+    // Binds in effect, the selected widget's first
+    pub(crate) fn binds(&mut self) -> Vec<&Bind> {
+        let (attr, children) = self.split_mut();
+        let selected = selected_in(children).map(|w| &w.style().binds);
+
+        selected
+            .into_iter()
+            .flat_map(|b| b.iter())
+            .chain(attr.binds.iter())
+            .collect()
+    }
+
+    // Takes on a frame built again, like a program's view, keeping focus and leaving what
+    // didn't change as it was
+    pub(crate) fn reconcile(&mut self, view: Frame) {
+        reconcile(self, view.attr, view.children.0);
     }
 }
 
 impl Panel for Frame {
     panel_shared!();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::{binds::Binds, key::Key},
+        style::color::{Color, ColorBG},
+        testing::render_frame,
+        widget::label::Label,
+    };
+
+    const SNAPSHOTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/panel/snapshots");
+
+    #[test]
+    fn centered_with_title_and_binds() {
+        let mut frame = Frame::new(
+            Attr::new()
+                .center()
+                .title("Demo")
+                .binds(Binds::new().bind(Key::q.into(), "quit"))
+                .border_color(Color::Cyan)
+                .wrap(),
+        );
+        frame.add(Label::new(
+            "hi",
+            Attr::new()
+                .size(6usize, 3usize)
+                .fill(ColorBG::Blue)
+                .center()
+                .wrap(),
+        ));
+
+        render_frame(&mut frame, 20, 7)
+            .assert_styled_snapshot(format!("{SNAPSHOTS}/centered_with_title_and_binds.snap"));
+    }
+}
@@ -1,16 +1,141 @@
+pub mod focus;
 pub mod frame;
+mod reconcile;
+pub mod route;
 
 use crate::{
+    draw::buffer::Buffer,
     style::{
-        self,
         align::{AlignX, AlignY},
+        color::ColorBG,
         orientation::Orientation,
-        set_style,
     },
-    unit::{Point, Unit},
-    widget::{Widget, attr::Attr},
+    unit::{Point, Rect, Unit},
+    widget::{
+        Widget,
+        attr::{Attr, Dirty},
+    },
 };
 
+// Returns whether the widget moved or changed size since it was last drawn
+fn record_rect(widget: &mut Box<dyn Widget>, anchor: Point) -> bool {
+    let attr = widget.style_mut();
+    let rect = Some(Rect::new(
+        anchor.x.calc(),
+        anchor.y.calc(),
+        attr.width.calc(),
+        attr.height.calc(),
+    ));
+
+    std::mem::replace(&mut attr.rect, rect) != rect
+}
+
+// Blanks what was drawn in rect before a widget is drawn there again
+fn clear_rect(buf: &mut Buffer, rect: Rect, fill: ColorBG) {
+    buf.reset();
+    buf.set_style(fill);
+
+    let blank = " ".repeat(rect.width);
+
+    for y in rect.y..rect.y + rect.height {
+        buf.go(Point::new(rect.x, y));
+        buf.print(&blank);
+    }
+}
+
+// Works out what has to be drawn before a frame, bottom up. Panels whose children were resized,
+// shown or hidden are repainted whole, since widgets around them move. Returns whether anything
+// under panel has to be drawn.
+pub(crate) fn settle(panel: &mut dyn Panel) -> bool {
+    let (attr, children) = panel.split_mut();
+    let mut below = false;
+    let mut relayout = false;
+
+    for child in children.iter_mut() {
+        // Uncovers whatever it was drawn over. What's inside waits until it's shown again.
+        if child.style().hide {
+            relayout |= child.style().rect.is_some();
+            continue;
+        }
+
+        if let Some(panel) = child.as_panel() {
+            let dirty = settle(panel);
+            child.style_mut().dirty_below = dirty;
+        }
+
+        let style = child.style();
+
+        relayout |= style.dirty == Dirty::Layout
+            || style
+                .rect
+                .is_none_or(|r| (r.width, r.height) != (style.width.calc(), style.height.calc()));
+        below |= style.is_dirty() || style.dirty_below;
+    }
+
+    if relayout {
+        attr.mark(Dirty::Paint);
+    }
+
+    below
+}
+
+// Innermost selected widget among children and what's inside them
+pub(crate) fn selected_in(children: &mut [Box<dyn Widget>]) -> Option<&mut Box<dyn Widget>> {
+    for child in children.iter_mut() {
+        if child.as_panel().is_some_and(|p| p.selected().is_some()) {
+            return child.as_panel().unwrap().selected();
+        }
+
+        if child.style().selected {
+            return Some(child);
+        }
+    }
+
+    None
+}
+
+// Widget reached by following child indices down from panel
+pub(crate) fn widget_at_path<'a>(
+    panel: &'a mut dyn Panel,
+    path: &[usize],
+) -> Option<&'a mut Box<dyn Widget>> {
+    let (_, children) = panel.split_mut();
+    let child = children.get_mut(*path.first()?)?;
+
+    if path.len() == 1 {
+        Some(child)
+    } else {
+        widget_at_path(child.as_panel()?, &path[1..])
+    }
+}
+
+// Draws the child at pos if it or anything inside it changed since the last frame
+fn draw_child(
+    child: &mut Box<dyn Widget>,
+    pos: Point,
+    background: ColorBG,
+    repaint: bool,
+    buf: &mut Buffer,
+) {
+    let moved = record_rect(child, pos);
+
+    if repaint || moved || child.style().is_dirty() {
+        // A repainted parent was blanked already
+        if !repaint && let Some(rect) = child.style().rect {
+            clear_rect(buf, rect, background);
+        }
+
+        child.style_mut().mark(Dirty::Paint);
+        child.outline(pos, buf);
+
+        buf.set_style(child.style().fill);
+        child.render(pos, buf);
+    } else if child.style().dirty_below {
+        buf.set_style(child.style().fill);
+        child.render(pos, buf);
+    }
+}
+
 pub trait Panel {
     fn split(&self) -> (&Attr, &Vec<Box<dyn Widget>>);
     fn split_mut(&mut self) -> (&mut Attr, &mut Vec<Box<dyn Widget>>);
@@ -57,6 +182,12 @@ pub trait Panel {
         None
     }
 
+    // Innermost selected widget
+    fn selected(&mut self) -> Option<&mut Box<dyn Widget>> {
+        let (_, children) = self.split_mut();
+        selected_in(children)
+    }
+
     fn child_at(&mut self, index: usize) -> Option<&mut Box<dyn Widget>> {
         let (_, children) = self.split_mut();
 
@@ -64,14 +195,17 @@ pub trait Panel {
     }
 
     fn add(&mut self, widget: Box<dyn Widget>) {
-        let (_, children) = self.split_mut();
+        let (attr, children) = self.split_mut();
 
+        attr.mark(Dirty::Layout);
         children.push(widget);
         self.flex();
     }
 
     fn addm(&mut self, widgets: Vec<Box<dyn Widget>>) {
-        let (_, children) = self.split_mut();
+        let (attr, children) = self.split_mut();
+
+        attr.mark(Dirty::Layout);
 
         for widget in widgets {
             children.push(widget);
@@ -81,7 +215,9 @@ pub trait Panel {
     }
 
     fn remove(&mut self, tag: &str) -> Option<Box<dyn Widget>> {
-        let (_, children) = self.split_mut();
+        let (attr, children) = self.split_mut();
+
+        attr.mark(Dirty::Layout);
 
         let mut removed = None;
 
@@ -95,7 +231,9 @@ pub trait Panel {
     }
 
     fn removem(&mut self, tags: Vec<&str>) -> Vec<Option<Box<dyn Widget>>> {
-        let (_, children) = self.split_mut();
+        let (attr, children) = self.split_mut();
+
+        attr.mark(Dirty::Layout);
 
         let mut removed = Vec::new();
 
@@ -112,6 +250,22 @@ pub trait Panel {
         removed
     }
 
+    // Flexes every panel inside this one, innermost first, then this one. For when sizes of
+    // what's inside changed, like percentages after a resize.
+    fn relayout(&mut self) {
+        let (_, children) = self.split_mut();
+
+        for child in children.iter_mut() {
+            if let Some(panel) = child.as_panel() {
+                panel.relayout();
+            }
+
+            child.style_mut().mark(Dirty::Layout);
+        }
+
+        self.flex();
+    }
+
     fn bounds(&self) -> (Unit, Unit) {
         let (attr, children) = self.split();
 
@@ -183,13 +337,19 @@ pub trait Panel {
         }
     }
 
-    fn render_children(&mut self, anchor: Point) {
+    fn render_children(&mut self, anchor: Point, buf: &mut Buffer) {
         let (inner_x, inner_y) = self.bounds();
         let (attr, children) = self.split_mut();
         let mut pos = anchor;
 
+        // Everything inside a panel is drawn again along with it, otherwise only children
+        // that changed or have changes inside them
+        let repaint = attr.is_dirty();
+
         for child in children {
-            if !child.style().hide {
+            if child.style().hide {
+                child.style_mut().rect = None;
+            } else {
                 match attr.orientation {
                     Orientation::Horizontal => {
                         pos.y += match attr.aligny {
@@ -206,10 +366,7 @@ pub trait Panel {
                             child.style_mut().should_fill = true;
                         }
 
-                        child.outline(pos);
-
-                        set_style(child.style().fill);
-                        child.render(pos);
+                        draw_child(child, pos, attr.fill, repaint, buf);
 
                         pos.y = anchor.y;
                         pos.x += child.style().width + child.style().padding_right
@@ -229,25 +386,36 @@ pub trait Panel {
                             child.style_mut().should_fill = true;
                         }
 
-                        child.outline(pos);
-
-                        set_style(child.style().fill);
-                        child.render(pos);
+                        draw_child(child, pos, attr.fill, repaint, buf);
 
                         pos.x = anchor.x;
                         pos.y += child.style().height + child.style().padding_bottom
                     }
                 }
 
-                style::reset();
+                buf.reset();
             }
+
+            child.style_mut().dirty = Dirty::Clean;
+            child.style_mut().dirty_below = false;
         }
     }
 
+    // Fits the panel to its children, starting from the size it was given so percentages follow
+    // the terminal
     fn flex(&mut self) {
         let (inner_x, inner_y) = self.bounds();
         let (attr, _) = self.split_mut();
 
+        let size = (attr.width, attr.height);
+        let base = match attr.base_size {
+            Some(base) if attr.fitted_size == Some(size) => base,
+            _ => size,
+        };
+
+        attr.base_size = Some(base);
+        (attr.width, attr.height) = base;
+
         if inner_x.calc() >= attr.width.calc() {
             attr.width = inner_x + Unit::CoR(2);
         }
@@ -265,5 +433,7 @@ pub trait Panel {
                 attr.height = inner_y + Unit::CoR(2);
             }
         }
+
+        attr.fitted_size = Some((attr.width, attr.height));
     }
 }
@@ -0,0 +1,225 @@
+use super::Panel;
+use crate::widget::{
+    Widget,
+    attr::{Attr, Dirty},
+};
+use std::any::Any;
+
+// Whether new is the same widget as old built again
+fn same(old: &dyn Widget, new: &dyn Widget) -> bool {
+    Any::type_id(old.as_any()) == Any::type_id(new.as_any()) && old.style().tag == new.style().tag
+}
+
+// Puts what changed in new into old. Unchanged widgets are left alone so they aren't drawn
+// again.
+fn update(old: &mut Box<dyn Widget>, mut new: Box<dyn Widget>) {
+    if let Some(panel) = new.as_panel() {
+        let (attr, children) = panel.split_mut();
+        let (attr, children) = (attr.clone(), std::mem::take(children));
+
+        // Same type, so a panel as well
+        if let Some(old) = old.as_panel() {
+            reconcile(old, attr, children);
+        }
+
+        return;
+    }
+
+    new.style_mut().keep_state(old.style());
+
+    if !new.weq(old.as_ref()) {
+        let dirty = if new.style().layout_differs(old.style()) {
+            Dirty::Layout
+        } else {
+            Dirty::Paint
+        };
+
+        new.style_mut().mark(dirty);
+        *old = new;
+    }
+}
+
+// Children built again take the place of the ones they were built from: tagged widgets wherever
+// they were, the rest by position. Returns whether any were added, removed or moved.
+fn reconcile_children(old: &mut Vec<Box<dyn Widget>>, new: Vec<Box<dyn Widget>>) -> bool {
+    let mut pool: Vec<Option<Box<dyn Widget>>> =
+        std::mem::take(old).into_iter().map(Some).collect();
+    let mut moved = pool.len() != new.len();
+
+    for (i, widget) in new.into_iter().enumerate() {
+        let matches = |w: &Option<Box<dyn Widget>>| {
+            w.as_ref()
+                .is_some_and(|w| same(w.as_ref(), widget.as_ref()))
+        };
+
+        let found = if widget.style().tag.is_empty() {
+            pool.get(i).is_some_and(matches).then_some(i)
+        } else {
+            pool.iter().position(matches)
+        };
+
+        match found.and_then(|j| Some((j, pool[j].take()?))) {
+            Some((j, mut kept)) => {
+                moved |= j != i;
+                update(&mut kept, widget);
+                old.push(kept);
+            }
+            None => {
+                moved = true;
+                old.push(widget);
+            }
+        }
+    }
+
+    moved
+}
+
+// Brings panel in line with the attr and children it was built with again, keeping focus,
+// where widgets were drawn and what's still up to date
+pub(crate) fn reconcile(panel: &mut dyn Panel, attr: Attr, children: Vec<Box<dyn Widget>>) {
+    let (old_attr, old_children) = panel.split_mut();
+
+    if reconcile_children(old_children, children) {
+        old_attr.mark(Dirty::Layout);
+    }
+
+    let declared = attr.declared();
+
+    if declared != old_attr.declared() {
+        let dirty = if declared.layout_differs(&old_attr.declared()) {
+            Dirty::Layout
+        } else {
+            Dirty::Paint
+        };
+
+        let mut attr = declared;
+        attr.keep_state(old_attr);
+        attr.mark(dirty);
+        *old_attr = attr;
+    }
+
+    // Children may have changed size even if this panel didn't
+    panel.flex();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        backend::headless::HeadlessBackend,
+        draw::screen::Screen,
+        panel::frame::Frame,
+        widget::{container::Container, label::Label},
+    };
+
+    fn view(first: &str, second: &str, swap: bool) -> Frame {
+        let mut list = Container::new(Attr::new().tag("list").wrap());
+        let mut labels = vec![
+            Label::new(
+                first,
+                Attr::new()
+                    .tag("first")
+                    .size(8usize, 3usize)
+                    .focusable()
+                    .wrap(),
+            ),
+            Label::new(
+                second,
+                Attr::new()
+                    .tag("second")
+                    .size(8usize, 3usize)
+                    .focusable()
+                    .wrap(),
+            ),
+        ];
+
+        if swap {
+            labels.reverse();
+        }
+
+        list.addm(labels.into_iter().map(|l| l as Box<dyn Widget>).collect());
+
+        let mut frame = Frame::new(None);
+        frame.addm(vec![
+            Label::new("title", Attr::new().size(8usize, 3usize).wrap()),
+            list,
+        ]);
+        frame
+    }
+
+    fn drawn(first: &str, second: &str) -> Frame {
+        let mut frame = view(first, second, false);
+        frame.focus("second");
+
+        let mut screen = Screen::new(HeadlessBackend::new(30, 20));
+        screen.render(&mut frame);
+        frame
+    }
+
+    fn text(frame: &mut Frame, tag: &str) -> String {
+        let label = frame.get_child(tag).unwrap();
+        label.as_any().downcast_ref::<Label>().unwrap().text.clone()
+    }
+
+    #[test]
+    fn same_view_changes_nothing() {
+        let mut frame = drawn("a", "b");
+        let before = frame.clone();
+
+        frame.reconcile(view("a", "b", false));
+
+        assert!(!frame.settle());
+        assert!(frame == before);
+    }
+
+    #[test]
+    fn only_changed_widgets_are_marked() {
+        let mut frame = drawn("a", "b");
+        frame.reconcile(view("a", "c", false));
+
+        assert_eq!(text(&mut frame, "second"), "c");
+        assert!(frame.get_child("second").unwrap().style().is_dirty());
+        assert!(frame.get_child("second").unwrap().style().rect().is_some());
+        assert!(!frame.get_child("first").unwrap().style().is_dirty());
+        assert!(!frame.get_child("list").unwrap().style().is_dirty());
+        assert_eq!(frame.focused().unwrap().style().tag, "second");
+    }
+
+    #[test]
+    fn tagged_widgets_keep_focus_when_moved() {
+        let mut frame = drawn("a", "b");
+        frame.reconcile(view("a", "b", true));
+
+        let list = frame.get_child("list").unwrap();
+        assert_eq!(list.style().dirty, Dirty::Layout);
+
+        let order: Vec<_> = list
+            .as_panel()
+            .unwrap()
+            .split()
+            .1
+            .iter()
+            .map(|w| w.style().tag.clone())
+            .collect();
+        assert_eq!(order, ["second", "first"]);
+        assert_eq!(frame.focused().unwrap().style().tag, "second");
+    }
+
+    #[test]
+    fn removed_and_added_widgets() {
+        let mut frame = drawn("a", "b");
+
+        let mut smaller = Frame::new(None);
+        smaller.add(Label::new(
+            "new",
+            Attr::new().tag("new").size(8usize, 3usize).wrap(),
+        ));
+        frame.reconcile(smaller);
+
+        let (attr, children) = frame.split();
+        assert_eq!(attr.dirty, Dirty::Layout);
+        assert_eq!(children.len(), 1);
+        assert_eq!(text(&mut frame, "new"), "new");
+        assert!(frame.focused().is_none());
+    }
+}
@@ -0,0 +1,211 @@
+use super::{Panel, frame::Frame};
+use crate::{
+    input::Input,
+    unit::Point,
+    widget::{Phase, Widget},
+};
+
+// Deepest visible widget whose outline covers (x, y), going by where it was last drawn
+fn path_at(panel: &mut dyn Panel, x: usize, y: usize, path: &mut Vec<usize>) -> bool {
+    let (_, children) = panel.split_mut();
+
+    // Later children are drawn over earlier ones
+    for (i, child) in children.iter_mut().enumerate().rev() {
+        if child.style().hide || !child.style().rect.is_some_and(|r| r.contains(x, y)) {
+            continue;
+        }
+
+        path.push(i);
+
+        if let Some(panel) = child.as_panel() {
+            path_at(panel, x, y, path);
+        }
+
+        return true;
+    }
+
+    false
+}
+
+impl Frame {
+    pub(crate) fn path_at(&mut self, pos: Point) -> Option<Vec<usize>> {
+        let mut path = vec![];
+        path_at(self, pos.x.calc(), pos.y.calc(), &mut path).then_some(path)
+    }
+
+    pub fn widget_at(&mut self, pos: Point) -> Option<&mut Box<dyn Widget>> {
+        let path = self.path_at(pos)?;
+        self.widget_at_path(&path)
+    }
+
+    // Sends a mouse input down through the containers around the widget under it, to the
+    // widget, then back up. Returns whether a widget handled it.
+    pub fn route(&mut self, input: &Input) -> bool {
+        let Input::Mouse(.., pos) = input else {
+            return false;
+        };
+
+        let Some(path) = self.path_at(*pos) else {
+            return false;
+        };
+
+        let mut handle = |depth: usize, phase: Phase| {
+            self.widget_at_path(&path[..depth])
+                .is_some_and(|w| w.handle(input, phase))
+        };
+
+        (1..path.len()).any(|depth| handle(depth, Phase::Capture))
+            || handle(path.len(), Phase::Target)
+            || (1..path.len())
+                .rev()
+                .any(|depth| handle(depth, Phase::Bubble))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        draw::buffer::Buffer,
+        impl_widget_base,
+        input::mouse::{Mouse, MouseState},
+        panel_shared,
+        testing::render_frame,
+        widget::{WidgetList, attr::Attr, label::Label},
+    };
+    use std::any::Any;
+
+    // Container that records what it was routed and handles it in one phase
+    #[derive(Clone, PartialEq)]
+    struct Probe {
+        attr: Attr,
+        children: WidgetList,
+        seen: Vec<Phase>,
+        stop: Option<Phase>,
+    }
+
+    impl Probe {
+        fn new(tag: &str, stop: Option<Phase>) -> Box<Probe> {
+            Box::new(Probe {
+                attr: Attr::new().tag(tag).size(5usize, 3usize).to_owned(),
+                children: [].into(),
+                seen: vec![],
+                stop,
+            })
+        }
+    }
+
+    impl_widget_base!(Probe);
+
+    impl Panel for Probe {
+        panel_shared!();
+    }
+
+    impl Widget for Probe {
+        fn as_panel(&mut self) -> Option<&mut dyn Panel> {
+            Some(self)
+        }
+
+        fn render(&mut self, anchor: Point, buf: &mut Buffer) {
+            self.render_children((anchor, 1, 1).into(), buf);
+        }
+
+        fn handle(&mut self, _: &Input, phase: Phase) -> bool {
+            self.seen.push(phase);
+            self.stop == Some(phase)
+        }
+    }
+
+    // outer > inner > leaf, plus a label beside outer
+    fn tree(outer_stop: Option<Phase>, leaf_stop: Option<Phase>) -> Frame {
+        let mut inner = Probe::new("inner", None);
+        inner.add(Probe::new("leaf", leaf_stop));
+
+        let mut outer = Probe::new("outer", outer_stop);
+        outer.add(inner);
+
+        let mut frame = Frame::new(None);
+        frame.addm(vec![
+            outer,
+            Label::new("x", Attr::new().tag("x").size(3usize, 3usize).wrap()),
+        ]);
+
+        render_frame(&mut frame, 20, 20);
+        frame
+    }
+
+    fn seen(frame: &mut Frame, tag: &str) -> Vec<Phase> {
+        let widget = frame.get_child(tag).unwrap();
+        widget
+            .as_any()
+            .downcast_ref::<Probe>()
+            .unwrap()
+            .seen
+            .clone()
+    }
+
+    fn click_at(frame: &mut Frame, tag: &str) -> Input {
+        let rect = frame.get_child(tag).unwrap().style().rect().unwrap();
+        Input::Mouse(
+            Mouse::Left,
+            MouseState::Click,
+            [].into(),
+            (rect.x + 1, rect.y + 1).into(),
+        )
+    }
+
+    #[test]
+    fn hit_test() {
+        let mut frame = tree(None, None);
+        let rect = frame.get_child("leaf").unwrap().style().rect().unwrap();
+
+        let hit = frame.widget_at((rect.x, rect.y).into()).unwrap();
+        assert_eq!(hit.style().tag, "leaf");
+
+        // Border of the container around it
+        let outer = frame.get_child("outer").unwrap().style().rect().unwrap();
+        let hit = frame.widget_at((outer.x, outer.y).into()).unwrap();
+        assert_eq!(hit.style().tag, "outer");
+
+        assert!(frame.widget_at((1, 1).into()).is_none());
+    }
+
+    #[test]
+    fn capture_target_bubble() {
+        let mut frame = tree(None, None);
+        let click = click_at(&mut frame, "leaf");
+
+        assert!(!frame.route(&click));
+        assert_eq!(seen(&mut frame, "outer"), [Phase::Capture, Phase::Bubble]);
+        assert_eq!(seen(&mut frame, "inner"), [Phase::Capture, Phase::Bubble]);
+        assert_eq!(seen(&mut frame, "leaf"), [Phase::Target]);
+    }
+
+    #[test]
+    fn handled_stops_routing() {
+        let mut frame = tree(Some(Phase::Capture), None);
+        let click = click_at(&mut frame, "leaf");
+
+        assert!(frame.route(&click));
+        assert_eq!(seen(&mut frame, "outer"), [Phase::Capture]);
+        assert!(seen(&mut frame, "inner").is_empty());
+        assert!(seen(&mut frame, "leaf").is_empty());
+
+        let mut frame = tree(None, Some(Phase::Target));
+        let click = click_at(&mut frame, "leaf");
+
+        assert!(frame.route(&click));
+        assert_eq!(seen(&mut frame, "outer"), [Phase::Capture]);
+        assert_eq!(seen(&mut frame, "leaf"), [Phase::Target]);
+    }
+
+    #[test]
+    fn hidden_widgets_are_skipped() {
+        let mut frame = tree(None, None);
+        let click = click_at(&mut frame, "leaf");
+
+        frame.get_child("outer").unwrap().style_mut().hide = true;
+        assert!(!frame.route(&click));
+        assert!(seen(&mut frame, "leaf").is_empty());
+    }
+}
@@ -55,8 +55,16 @@ impl SceneHandler {
     }
 
     pub fn set<T: SceneKeyT>(&mut self, key: &mut T) {
+        self.set_dyn(key);
+    }
+
+    // Returns whether there was a scene with the key
+    pub(crate) fn set_dyn(&mut self, key: &dyn SceneKey) -> bool {
         if let Some(i) = self.scenes.iter().position(|s| s.key.eq(key)) {
             self.current_pos = i;
+            true
+        } else {
+            false
         }
     }
 
@@ -74,10 +82,12 @@ mod test {
     };
 
     // Helper keys for testing
+    #[allow(non_camel_case_types)]
     #[derive(Clone, Copy, PartialEq, Eq, Debug)]
     struct keyA(u32);
     make_scene_key!(keyA);
 
+    #[allow(non_camel_case_types)]
     #[derive(Clone, Copy, PartialEq, Eq, Debug)]
     struct keyB(u32);
     make_scene_key!(keyB);
@@ -109,7 +119,7 @@ mod test {
         let f1 = Frame::new(None);
         let f2 = f1.clone();
 
-        let mut k1 = keyA(1);
+        let k1 = keyA(1);
         let mut k2 = keyA(2);
 
         handler.add(k1, f1);
@@ -156,7 +166,7 @@ mod test {
     #[test]
     fn set_nonexistent_key_does_not_change_current() {
         let mut handler = SceneHandler::new();
-        let mut k1 = keyA(1);
+        let k1 = keyA(1);
         let mut k2 = keyA(2);
         handler.add(k1, Frame::new(None));
         handler.add(k2, Frame::new(None));
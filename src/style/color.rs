@@ -51,6 +51,10 @@ impl super::PrintableStyle for Color {
             Color::RGB(r, g, b) => format!("\x1b[38;2;{};{};{}m", r, g, b),
         }
     }
+
+    fn apply(&self, style: &mut super::Style) {
+        style.fg = *self;
+    }
 }
 
 // Background
@@ -104,4 +108,8 @@ impl super::PrintableStyle for ColorBG {
             ColorBG::RGB(r, g, b) => format!("\x1b[48;2;{};{};{}m", r, g, b),
         }
     }
+
+    fn apply(&self, style: &mut super::Style) {
+        style.bg = *self;
+    }
 }
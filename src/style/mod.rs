@@ -5,9 +5,14 @@ pub mod orientation;
 pub mod text;
 
 use crate::printf;
+use color::{Color, ColorBG};
+use text::{TextStyle, TextStyles};
 
 pub trait PrintableStyle {
     fn print(&self) -> String;
+
+    // Update a cell style the same way printing would update the terminal
+    fn apply(&self, style: &mut Style);
 }
 
 pub fn set_style(s: impl PrintableStyle) {
@@ -17,3 +22,81 @@ pub fn set_style(s: impl PrintableStyle) {
 pub fn reset() {
     printf!("\x1b[0m");
 }
+
+// Complete style of a single cell
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: ColorBG,
+    pub text: TextStyles,
+}
+
+impl Style {
+    pub fn new() -> Style {
+        Style {
+            fg: Color::None,
+            bg: ColorBG::None,
+            text: TextStyles::new(),
+        }
+    }
+
+    pub fn has(&self, s: TextStyle) -> bool {
+        self.text.contains(s)
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::new()
+    }
+}
+
+impl PrintableStyle for Style {
+    fn print(&self) -> String {
+        format!(
+            "\x1b[0m{}{}{}",
+            self.fg.print(),
+            self.bg.print(),
+            self.text.print()
+        )
+    }
+
+    fn apply(&self, style: &mut Style) {
+        *style = *self;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn apply_colors() {
+        let mut s = Style::new();
+        Color::Red.apply(&mut s);
+        ColorBG::Blue.apply(&mut s);
+        assert_eq!(s.fg, Color::Red);
+        assert_eq!(s.bg, ColorBG::Blue);
+    }
+
+    #[test]
+    fn apply_text_styles() {
+        let mut s = Style::new();
+        TextStyle::Bold.apply(&mut s);
+        TextStyle::Italic.apply(&mut s);
+        assert!(s.has(TextStyle::Bold));
+        assert!(s.has(TextStyle::Italic));
+
+        TextStyle::NoBold.apply(&mut s);
+        assert!(!s.has(TextStyle::Bold));
+        assert!(s.has(TextStyle::Italic));
+    }
+
+    #[test]
+    fn print_starts_from_reset() {
+        let mut s = Style::new();
+        Color::Green.apply(&mut s);
+        TextStyle::Underline.apply(&mut s);
+        assert_eq!(s.print(), "\x1b[0m\x1b[32m\x1b[49m\x1b[4m");
+    }
+}
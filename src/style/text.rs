@@ -38,4 +38,79 @@ impl super::PrintableStyle for TextStyle {
             TextStyle::NoStrikethrough => String::from("\x1b[29m"),
         }
     }
+
+    fn apply(&self, style: &mut super::Style) {
+        match self {
+            // Same as the escape code, 22 turns off both bold and dim
+            TextStyle::NoBold | TextStyle::NoDim => {
+                style.text.0 &= !(TextStyle::Bold.bit() | TextStyle::Dim.bit())
+            }
+            TextStyle::NoItalic
+            | TextStyle::NoUnderline
+            | TextStyle::NoBlinking
+            | TextStyle::NoInverse
+            | TextStyle::NoHidden
+            | TextStyle::NoStrikethrough => style.text.0 &= !self.bit(),
+            _ => style.text.0 |= self.bit(),
+        }
+    }
+}
+
+impl TextStyle {
+    // Bit used in TextStyles, the No* variants share the bit of the style they turn off
+    fn bit(&self) -> u8 {
+        match self {
+            TextStyle::Bold | TextStyle::NoBold => 0b0000_0001,
+            TextStyle::Dim | TextStyle::NoDim => 0b0000_0010,
+            TextStyle::Italic | TextStyle::NoItalic => 0b0000_0100,
+            TextStyle::Underline | TextStyle::NoUnderline => 0b0000_1000,
+            TextStyle::Blinking | TextStyle::NoBlinking => 0b0001_0000,
+            TextStyle::Inverse | TextStyle::NoInverse => 0b0010_0000,
+            TextStyle::Hidden | TextStyle::NoHidden => 0b0100_0000,
+            TextStyle::Strikethrough | TextStyle::NoStrikethrough => 0b1000_0000,
+        }
+    }
+}
+
+const ON_STYLES: [TextStyle; 8] = [
+    TextStyle::Bold,
+    TextStyle::Dim,
+    TextStyle::Italic,
+    TextStyle::Underline,
+    TextStyle::Blinking,
+    TextStyle::Inverse,
+    TextStyle::Hidden,
+    TextStyle::Strikethrough,
+];
+
+// Set of text styles currently turned on
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct TextStyles(u8);
+
+impl TextStyles {
+    pub fn new() -> TextStyles {
+        TextStyles(0)
+    }
+
+    pub fn contains(&self, s: TextStyle) -> bool {
+        ON_STYLES.contains(&s) && self.0 & s.bit() != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl super::PrintableStyle for TextStyles {
+    fn print(&self) -> String {
+        ON_STYLES
+            .iter()
+            .filter(|s| self.contains(**s))
+            .map(|s| s.print())
+            .collect()
+    }
+
+    fn apply(&self, style: &mut super::Style) {
+        style.text = *self;
+    }
 }
@@ -0,0 +1,757 @@
+use super::snapshot::Snapshot;
+use crate::{
+    app::App,
+    backend::headless::HeadlessBackend,
+    input::{
+        Input,
+        key::Key,
+        mouse::{Mouse, MouseState},
+    },
+    widget::Widget,
+};
+
+// Drives a headless App frame by frame. Inputs are queued and handed to the app on the
+// next step, nothing reads stdin or sleeps.
+pub struct AppDriver<S = ()> {
+    app: App<S>,
+    queue: Vec<Input>,
+    started: bool,
+    running: bool,
+    frames: usize,
+}
+
+impl AppDriver {
+    pub fn new(width: usize, height: usize) -> AppDriver {
+        AppDriver::with(App::headless(width, height))
+    }
+}
+
+impl<S> AppDriver<S> {
+    // The app must have been created with App::headless or App::headless_with_state
+    pub fn with(app: App<S>) -> AppDriver<S> {
+        AppDriver {
+            app,
+            queue: vec![],
+            started: false,
+            running: true,
+            frames: 0,
+        }
+    }
+
+    pub fn app(&mut self) -> &mut App<S> {
+        &mut self.app
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    // Frames stepped so far
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn input(&mut self, input: impl Into<Input>) -> &mut Self {
+        self.queue.push(input.into());
+        self
+    }
+
+    pub fn inputs(&mut self, inputs: impl IntoIterator<Item = Input>) -> &mut Self {
+        self.queue.extend(inputs);
+        self
+    }
+
+    pub fn key(&mut self, key: Key) -> &mut Self {
+        self.input(key)
+    }
+
+    // Every char of s as a key press
+    pub fn type_str(&mut self, s: &str) -> &mut Self {
+        for c in s.chars() {
+            if let Some(key) = Key::from_char(c) {
+                self.input(key);
+            }
+        }
+
+        self
+    }
+
+    pub fn paste(&mut self, text: &str) -> &mut Self {
+        self.input(Input::Paste(text.into()))
+    }
+
+    // Press and release at (x, y), 1-based like the terminal reports it
+    pub fn click(&mut self, button: Mouse, x: usize, y: usize) -> &mut Self {
+        self.input(Input::Mouse(
+            button,
+            MouseState::Click,
+            [].into(),
+            (x, y).into(),
+        ));
+        self.input(Input::Mouse(
+            button,
+            MouseState::Release,
+            [].into(),
+            (x, y).into(),
+        ))
+    }
+
+    // Resizes the backend and queues Input::Resize, like SIGWINCH does for the terminal
+    pub fn resize(&mut self, width: usize, height: usize) -> &mut Self {
+        if let Some(backend) = self
+            .app
+            .backend_mut()
+            .as_any_mut()
+            .downcast_mut::<HeadlessBackend>()
+        {
+            backend.resize(width, height);
+        }
+
+        self.input(Input::Resize(width, height))
+    }
+
+    // Ctrl + Z followed by fg, like the signals do for the terminal: run gets Input::Suspend
+    // right away and Input::Resume on the next step, when the screen is drawn again
+    pub fn suspend(&mut self) -> &mut Self {
+        self.start();
+
+        if self.running {
+            self.running = self.app.suspend();
+        }
+
+        self.app.resume();
+        self.input(Input::Resume)
+    }
+
+    // Runs init before the first frame
+    pub fn start(&mut self) -> &mut Self {
+        if !self.started {
+            self.started = true;
+            self.app.call_init();
+        }
+
+        self
+    }
+
+    // One frame with everything queued so far. Returns whether the app is still running.
+    pub fn step(&mut self) -> bool {
+        self.start();
+
+        if self.running {
+            let inputs = std::mem::take(&mut self.queue);
+            self.running = self.app.step(inputs);
+            self.frames += 1;
+        }
+
+        self.running
+    }
+
+    pub fn step_n(&mut self, n: usize) -> bool {
+        for _ in 0..n {
+            if !self.step() {
+                break;
+            }
+        }
+
+        self.running
+    }
+
+    // Runs end, consuming the driver
+    pub fn end(mut self) -> App<S> {
+        self.start();
+        self.app.call_end();
+        self.app
+    }
+
+    pub fn screen(&self) -> Snapshot {
+        Snapshot::new(self.app.screen().clone())
+    }
+
+    pub fn widget<T: Widget>(&mut self, tag: &str) -> Option<&mut T> {
+        self.app.get_widget(tag)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        app::{control::Control, option::AppOption},
+        input::{
+            binds::Binds,
+            key::{KeyState, Protocol},
+            keymap::Keymap,
+            modifier::Modifier,
+        },
+        make_action, make_scene_key,
+        panel::{Panel, frame::Frame},
+        scene::SceneKey,
+        unit::Unit,
+        widget::{attr::Attr, container::Container, label::Label},
+    };
+    use std::{
+        cell::RefCell,
+        process::Command,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    // Shows the count kept in the state
+    fn show(app: &mut App<usize>) {
+        let n = app.state;
+        app.get_widget::<Label>("count").unwrap().text = n.to_string();
+    }
+
+    fn counter() -> AppDriver<usize> {
+        let mut app = App::headless_with_state(12, 5, 0);
+
+        app.on_init(|app| {
+            app.current_frame().add(Label::new(
+                "0",
+                Attr::new().tag("count").size(5usize, 3usize).wrap(),
+            ));
+        });
+
+        app.on_run(|app, input| match input {
+            Some(Input::Key(Key::q, _, _)) => Control::Quit(1),
+            Some(Input::Key(Key::Plus, _, _))
+            | Some(Input::Mouse(
+                _,
+                MouseState::Click | MouseState::DoubleClick | MouseState::TripleClick,
+                _,
+                _,
+            )) => {
+                app.state += 1;
+                show(app);
+                Control::Continue
+            }
+            _ => Control::Continue,
+        });
+
+        AppDriver::with(app)
+    }
+
+    #[test]
+    fn first_step_runs_init_and_draws() {
+        let mut driver = counter();
+        assert!(driver.step());
+        assert_eq!(driver.frames(), 1);
+        assert_eq!(driver.screen().lines()[3], "║ │0  │    ║");
+    }
+
+    #[test]
+    fn inputs_apply_in_order() {
+        let mut driver = counter();
+        driver.key(Key::Plus).key(Key::Plus).step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "2");
+
+        driver.click(Mouse::Left, 3, 3).step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "3");
+        assert_eq!(driver.screen().lines()[3], "║ │3  │    ║");
+    }
+
+    #[test]
+    fn quit_stops_stepping() {
+        let mut driver = counter();
+        driver.type_str("+q+");
+        assert!(!driver.step());
+        assert!(!driver.step_n(3));
+        assert_eq!(driver.frames(), 1);
+        assert_eq!(driver.app().exit_code(), Some(1));
+
+        let mut app = driver.end();
+        assert_eq!(app.state, 1);
+        assert_eq!(app.get_widget::<Label>("count").unwrap().text, "1");
+    }
+
+    #[test]
+    fn delegates_capture_their_environment() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut app = App::headless(10, 5);
+
+        let seen = log.clone();
+        app.on_run(move |_, input| {
+            if let Some(input) = input {
+                seen.borrow_mut().push(input.to_string());
+            }
+            Control::Continue
+        });
+
+        let seen = log.clone();
+        app.on_end(move |_| seen.borrow_mut().push("end".into()));
+
+        let mut driver = AppDriver::with(app);
+        driver.key(Key::a).step();
+        driver.end();
+
+        assert_eq!(*log.borrow(), ["a", "end"]);
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    struct Help;
+
+    make_scene_key!(Help);
+
+    #[test]
+    fn switch_scene() {
+        let mut app = App::headless(10, 5);
+        app.scenes.add(Help, Frame::new(None));
+
+        app.on_run(|_, input| match input {
+            Some(Input::Key(Key::h, _, _)) => Control::switch(Help),
+            _ => Control::Continue,
+        });
+
+        let mut driver = AppDriver::with(app);
+        driver.step();
+        assert!(driver.app().current_scene_key::<Help>().is_none());
+
+        driver.key(Key::h).step();
+        assert!(driver.is_running());
+        assert_eq!(driver.app().current_scene_key::<Help>(), Some(&Help));
+    }
+
+    #[test]
+    fn double_click() {
+        let mut driver = counter();
+        driver
+            .click(Mouse::Left, 3, 3)
+            .click(Mouse::Left, 3, 3)
+            .step();
+
+        // Two clicks and the double click
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "3");
+
+        driver.click(Mouse::Left, 3, 3).step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "5");
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    enum Count {
+        Add(usize),
+        Reset,
+    }
+
+    make_action!(Count);
+
+    fn count_by(app: &mut App<usize>, action: &Count) -> Control {
+        app.state = match action {
+            Count::Add(n) => app.state + n,
+            Count::Reset => 0,
+        };
+
+        show(app);
+        Control::Continue
+    }
+
+    #[test]
+    fn bound_actions() {
+        let mut driver = counter();
+        driver.app().on(count_by);
+        driver.start();
+
+        driver.app().current_frame().attr.binds(Binds::new().action(
+            Key::a.into(),
+            "add",
+            Count::Add(10),
+        ));
+
+        // Bound input is handled, the rest still reaches run
+        driver.key(Key::a).key(Key::Plus).step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "11");
+
+        // Selected widget binds win over the frame's
+        driver
+            .widget::<Label>("count")
+            .unwrap()
+            .attr
+            .select()
+            .binds(Binds::new().action(Key::a.into(), "reset", Count::Reset));
+
+        driver.key(Key::a).step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "0");
+    }
+
+    #[test]
+    fn mouse_binds_match_anywhere() {
+        let mut driver = counter();
+        driver.app().on(count_by);
+        driver.start();
+
+        let keymap: Keymap = "add = mouse:left".parse().unwrap();
+        let binds = keymap.binds([("add", "add", Count::Add(10))]);
+        driver.app().current_frame().attr.binds(binds);
+
+        // The bind has no position, the click has one
+        driver.click(Mouse::Left, 7, 4).step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "10");
+    }
+
+    #[test]
+    fn sequence_actions() {
+        let mut driver = counter();
+        driver.app().on(count_by);
+        driver.start();
+
+        driver.app().current_frame().attr.binds(
+            Binds::new()
+                .action_sequence([Key::g.into(), Key::g.into()], "reset", Count::Reset)
+                .action_sequence([Key::g.into(), Key::a.into()], "add", Count::Add(5)),
+        );
+
+        driver.type_str("++g").step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "2");
+        assert_eq!(driver.app().pending_sequence(), [Input::from(Key::g)]);
+        assert_eq!(driver.app().pending_binds().len(), 2);
+
+        driver.type_str("a").step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "7");
+        assert!(driver.app().pending_sequence().is_empty());
+
+        driver.type_str("gg").step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "0");
+
+        // The prefix reaches run when the sequence breaks
+        driver.type_str("g+").step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "1");
+    }
+
+    #[test]
+    fn pending_prefix_goes_before_other_inputs() {
+        let mut app = App::headless_with_state(12, 5, vec![]);
+        app.on(|_, _: &Count| Control::Continue);
+        app.on_run(|app, input| {
+            if let Some(input) = input {
+                app.state.push(input.to_string());
+            }
+            Control::Continue
+        });
+
+        let mut driver = AppDriver::with(app);
+        driver.start();
+        driver
+            .app()
+            .current_frame()
+            .attr
+            .binds(Binds::new().action_sequence(
+                [Key::g.into(), Key::g.into()],
+                "reset",
+                Count::Reset,
+            ));
+
+        driver.key(Key::g).paste("x").step();
+        assert!(driver.app().pending_sequence().is_empty());
+        assert_eq!(driver.app().state, ["g", "Paste (1 chars)"]);
+    }
+
+    #[test]
+    fn focus_events() {
+        let mut driver = counter();
+        assert!(driver.app().is_focused());
+
+        driver.input(Input::FocusLost).step();
+        assert!(!driver.app().is_focused());
+
+        driver.input(Input::FocusGained).step();
+        assert!(driver.app().is_focused());
+    }
+
+    #[test]
+    fn widget_focus() {
+        let mut app = App::headless(24, 20);
+
+        app.on_init(|app| {
+            app.current_frame().addm(vec![
+                Label::new(
+                    "",
+                    Attr::new()
+                        .tag("name")
+                        .size(10usize, 3usize)
+                        .focusable()
+                        .wrap(),
+                ),
+                Label::new(
+                    "",
+                    Attr::new()
+                        .tag("mail")
+                        .size(10usize, 3usize)
+                        .focusable()
+                        .wrap(),
+                ),
+                Label::new("", Attr::new().tag("log").size(10usize, 3usize).wrap()),
+            ]);
+        });
+
+        // Shows the last focus change in the log
+        app.on_run(|app, input| {
+            if let Some(Input::FocusChanged(from, to)) = input {
+                app.get_widget::<Label>("log").unwrap().text =
+                    format!("{}>{}", from.unwrap_or_default(), to.unwrap_or_default());
+            }
+
+            Control::Continue
+        });
+
+        let mut driver = AppDriver::with(app);
+        let log = |d: &mut AppDriver| d.widget::<Label>("log").unwrap().text.clone();
+
+        // The first focusable widget takes focus right away
+        driver.step();
+        assert_eq!(driver.app().focused_tag().as_deref(), Some("name"));
+        assert_eq!(log(&mut driver), ">name");
+
+        driver.key(Key::Tab).step();
+        assert_eq!(driver.app().focused_tag().as_deref(), Some("mail"));
+        assert_eq!(log(&mut driver), "name>mail");
+
+        driver.input((Key::Tab, Modifier::Shift)).step();
+        assert_eq!(driver.app().focused_tag().as_deref(), Some("name"));
+
+        assert!(driver.app().focus("mail"));
+        assert!(!driver.app().focus("log"));
+        driver.step();
+        assert_eq!(log(&mut driver), "name>mail");
+
+        // Arrows only move focus once enabled
+        driver.key(Key::Up).step();
+        assert_eq!(driver.app().focused_tag().as_deref(), Some("mail"));
+
+        driver.app().opts.set(AppOption::ArrowFocus(true));
+        driver.key(Key::Up).step();
+        assert_eq!(driver.app().focused_tag().as_deref(), Some("name"));
+    }
+
+    #[test]
+    fn click_hit_test() {
+        let mut driver = counter();
+        driver.step();
+
+        let rect = driver
+            .widget::<Label>("count")
+            .unwrap()
+            .attr
+            .rect()
+            .unwrap();
+        assert_eq!(
+            driver.app().tag_at((rect.x, rect.y)).as_deref(),
+            Some("count")
+        );
+        assert_eq!(driver.app().tag_at((1, 1)), None);
+
+        driver.widget::<Label>("count").unwrap().attr.focusable();
+        driver.app().current_frame().add(Label::new(
+            "",
+            Attr::new()
+                .tag("other")
+                .size(3usize, 3usize)
+                .focusable()
+                .wrap(),
+        ));
+        driver.app().focus("other");
+
+        // Clicks focus what they hit and still reach run when no widget handles them
+        driver.click(Mouse::Left, rect.x + 1, rect.y + 1).step();
+        assert_eq!(driver.app().focused_tag().as_deref(), Some("count"));
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "1");
+    }
+
+    fn tick(app: &mut App<usize>) -> Control {
+        app.state += 1;
+        show(app);
+        Control::Continue
+    }
+
+    #[test]
+    fn timers() {
+        let mut driver = counter();
+        driver.step();
+
+        driver.app().after(Duration::ZERO, tick);
+        let id = driver.app().every(Duration::ZERO, tick);
+        driver
+            .app()
+            .after(Duration::from_secs(60), |_| Control::Quit(1));
+
+        driver.step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "2");
+
+        // Only the repeating one is left to fire now
+        driver.step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "3");
+
+        assert!(driver.app().cancel(id));
+        driver.step();
+        assert_eq!(driver.widget::<Label>("count").unwrap().text, "3");
+        assert!(driver.is_running());
+    }
+
+    #[test]
+    fn waits_for_next_deadline() {
+        let mut driver = counter();
+        driver.step();
+        assert_eq!(driver.app().next_deadline(), None);
+
+        let before = Instant::now();
+        driver.app().after(Duration::from_secs(60), tick);
+        let deadline = driver.app().next_deadline().unwrap();
+        assert!(deadline >= before + Duration::from_secs(60));
+
+        driver.app().opts.set(AppOption::RefreshRate(10));
+        driver.app().opts.set(AppOption::FixedRate(true));
+        driver.step();
+        let deadline = driver.app().next_deadline().unwrap();
+        assert!(deadline <= Instant::now() + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn resize_redraws_at_new_size() {
+        let mut driver = counter();
+        driver.step();
+        driver.resize(8, 6).step();
+
+        let screen = driver.screen();
+        assert_eq!((screen.width(), screen.height()), (8, 6));
+        assert_eq!(screen.lines()[5], "╚══════╝");
+    }
+
+    #[test]
+    fn resize_lays_out_again() {
+        // Resizes run got, and the size on_resize got last
+        let mut app = App::headless_with_state(20, 10, (0, None));
+
+        app.on_init(|app| {
+            let mut half = Container::new(Attr::new().tag("half").width(Unit::PctH(50)).wrap());
+            half.add(Label::new("", Attr::new().size(12usize, 3usize).wrap()));
+            app.current_frame().add(half);
+        });
+        app.on_resize(|app, w, h| app.state.1 = Some((w, h)));
+        app.on_run(|app, input| {
+            if let Some(Input::Resize(..)) = input {
+                app.state.0 += 1;
+            }
+            Control::Continue
+        });
+
+        let mut driver = AppDriver::with(app);
+        driver.step();
+
+        let width = |driver: &mut AppDriver<_>| {
+            let half = driver.app().current_frame().get_child("half").unwrap();
+            half.style().rect().unwrap().width
+        };
+
+        // Too small for the label at first, so it fits the label instead
+        assert_eq!(width(&mut driver), 16);
+
+        driver.resize(40, 10).step();
+        assert_eq!(width(&mut driver), 20);
+        assert_eq!(driver.app().state, (1, Some((40, 10))));
+    }
+
+    fn flushes(driver: &mut AppDriver<usize>) -> usize {
+        let backend = driver.app().backend_mut().as_any_mut();
+        backend.downcast_mut::<HeadlessBackend>().unwrap().flushes()
+    }
+
+    // Counts the suspends, resumes and interrupts run got in the hundreds, tens and ones
+    fn signals() -> AppDriver<usize> {
+        let mut driver = counter();
+
+        driver.app().on_run(|app, input| {
+            match input {
+                Some(Input::Suspend) => app.state += 100,
+                Some(Input::Resume) => app.state += 10,
+                Some(Input::Interrupt) => app.state += 1,
+                Some(Input::Key(Key::q, _, _)) => return Control::Quit(1),
+                _ => {}
+            }
+            Control::Continue
+        });
+
+        driver.step();
+        driver
+    }
+
+    #[test]
+    fn suspend_and_resume() {
+        let mut driver = signals();
+        let before = flushes(&mut driver);
+
+        driver.suspend();
+        assert_eq!(driver.app().state, 100);
+
+        // Nothing changed, but the screen is drawn again
+        driver.step();
+        assert_eq!(driver.app().state, 110);
+        assert_eq!(flushes(&mut driver), before + 1);
+    }
+
+    #[test]
+    fn quit_on_suspend() {
+        let mut driver = signals();
+        driver.app().on_run(|_, input| match input {
+            Some(Input::Suspend) => Control::Quit(2),
+            _ => Control::Continue,
+        });
+
+        assert!(!driver.suspend().step());
+        assert_eq!(driver.app().exit_code(), Some(2));
+    }
+
+    #[test]
+    fn kitty_ctrl_keys_act_as_signals() {
+        let mut driver = signals();
+        let ctrl = |key| Input::Key(key, KeyState::Press, Modifier::Ctrl.into());
+
+        // Plain keys without the protocol
+        driver.input(ctrl(Key::c)).input(ctrl(Key::z)).step();
+        assert_eq!(driver.app().state, 0);
+
+        driver
+            .app()
+            .opts
+            .set(AppOption::KeyProtocol(Protocol::Kitty));
+        driver.input(ctrl(Key::c)).step();
+        assert_eq!(driver.app().state, 0, "interrupts are ignored by default");
+
+        driver.app().opts.set(AppOption::InterruptInput(true));
+        driver.input(ctrl(Key::c)).input(ctrl(Key::z)).step();
+        assert_eq!(driver.app().state, 101);
+
+        // Releases and other modifiers are keys still
+        driver
+            .input(Input::Key(Key::c, KeyState::Release, Modifier::Ctrl.into()))
+            .input(Input::Key(
+                Key::z,
+                KeyState::Press,
+                [Modifier::Ctrl, Modifier::Shift].into(),
+            ))
+            .step();
+        assert_eq!(driver.app().state, 101);
+    }
+
+    #[test]
+    fn run_external() {
+        let mut driver = counter();
+        driver.step();
+
+        let before = flushes(&mut driver);
+
+        let status = driver
+            .app()
+            .run_external(Command::new("sh").args(["-c", "exit 3"]))
+            .unwrap();
+        assert_eq!(status.code(), Some(3));
+
+        // Nothing changed, but the screen is drawn again
+        driver.step();
+        assert_eq!(flushes(&mut driver), before + 1);
+
+        assert!(
+            driver
+                .app()
+                .run_external(&mut Command::new("/nonexistent"))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,65 @@
+pub mod driver;
+pub mod snapshot;
+
+use crate::{
+    app::Viewport, backend::headless::HeadlessBackend, draw::screen::Screen, panel::frame::Frame,
+    scene::Scene,
+};
+use snapshot::Snapshot;
+
+// Render a frame as if the terminal was width x height
+pub fn render_frame(frame: &mut Frame, width: usize, height: usize) -> Snapshot {
+    let _viewport = Viewport::set(Some((width, height)));
+    let mut screen = Screen::new(HeadlessBackend::new(width, height));
+    screen.render(frame);
+
+    Snapshot::new(screen.backend().buffer().clone())
+}
+
+pub fn render_scene(scene: &mut Scene, width: usize, height: usize) -> Snapshot {
+    render_frame(&mut scene.frame, width, height)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        app::get_tsz,
+        make_scene_key,
+        panel::Panel,
+        scene::SceneKey,
+        widget::{attr::Attr, label::Label},
+    };
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    struct Key;
+    make_scene_key!(Key);
+
+    #[test]
+    fn frame_at_size() {
+        let snap = render_frame(&mut Frame::new(None), 5, 3);
+        assert_eq!((snap.width(), snap.height()), (5, 3));
+        snap.assert_text("╔═══╗\n║   ║\n╚═══╝");
+    }
+
+    #[test]
+    fn scene_at_size() {
+        let mut frame = Frame::new(Attr::new().hide_border().wrap());
+        frame.add(Label::new("hi", Attr::new().size(4usize, 3usize).wrap()));
+
+        let snap = render_scene(&mut Scene::new(Key, frame), 7, 5);
+        snap.assert_text("       \n       \n  ┌──┐ \n  │hi│ \n  └──┘ ");
+    }
+
+    #[test]
+    fn viewport_is_put_back() {
+        let _outer = Viewport::set(Some((1, 2)));
+
+        render_frame(&mut Frame::new(None), 5, 3);
+        assert_eq!(get_tsz(), (1, 2));
+
+        let mut driver = driver::AppDriver::new(5, 3);
+        driver.step();
+        assert_eq!(get_tsz(), (1, 2));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{
+    draw::buffer::{Buffer, Cell},
+    style::Style,
+};
+use std::{fmt::Write, fs, path::Path};
+
+// Set to write snapshot files instead of comparing against them
+pub const UPDATE_VAR: &str = "BRANNON_UPDATE_SNAPSHOTS";
+
+// Rendered grid of a frame, as text plus the style of every cell
+#[derive(Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    grid: Buffer,
+}
+
+impl Snapshot {
+    pub fn new(grid: Buffer) -> Snapshot {
+        Snapshot { grid }
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> Option<&Cell> {
+        self.grid.get(x, y)
+    }
+
+    pub fn char_at(&self, x: usize, y: usize) -> Option<char> {
+        self.cell(x, y).map(|c| c.ch)
+    }
+
+    pub fn style_at(&self, x: usize, y: usize) -> Option<Style> {
+        self.cell(x, y).map(|c| c.style)
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.height())
+            .map(|y| {
+                (0..self.width())
+                    .filter_map(|x| self.cell(x, y))
+                    .filter(|c| !c.is_continuation())
+                    .map(|c| c.ch)
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        self.lines().join("\n")
+    }
+
+    // Distinct styles in order of first appearance
+    pub fn styles(&self) -> Vec<Style> {
+        let mut styles: Vec<Style> = vec![];
+
+        for cell in self.grid.cells() {
+            if !styles.contains(&cell.style) {
+                styles.push(cell.style);
+            }
+        }
+
+        styles
+    }
+
+    // Style of every cell as a letter indexing into styles(), followed by the legend
+    pub fn style_map(&self) -> String {
+        let styles = self.styles();
+        let mut out = String::new();
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let i = styles
+                    .iter()
+                    .position(|s| Some(*s) == self.style_at(x, y))
+                    .unwrap_or(0);
+
+                out.push(style_letter(i));
+            }
+
+            out.push('\n');
+        }
+
+        for (i, style) in styles.iter().enumerate() {
+            _ = writeln!(out, "{}: {:?}", style_letter(i), style);
+        }
+
+        out
+    }
+
+    // Text grid and style map, as stored by assert_styled_snapshot
+    pub fn styled(&self) -> String {
+        format!("{}\n---\n{}", self.text(), self.style_map())
+    }
+
+    pub fn assert_text(&self, expected: &str) {
+        assert_same(expected, &self.text(), "inline text");
+    }
+
+    pub fn assert_snapshot(&self, path: impl AsRef<Path>) {
+        compare_file(path.as_ref(), &self.text());
+    }
+
+    pub fn assert_styled_snapshot(&self, path: impl AsRef<Path>) {
+        compare_file(path.as_ref(), &self.styled());
+    }
+}
+
+fn style_letter(i: usize) -> char {
+    // a-z then A-Z, anything past that shares '?'
+    match i {
+        0..26 => (b'a' + i as u8) as char,
+        26..52 => (b'A' + (i - 26) as u8) as char,
+        _ => '?',
+    }
+}
+
+// Snapshots are only written when asked to, so a missing one fails instead of passing unchecked
+fn compare_file(path: &Path, actual: &str) {
+    if std::env::var_os(UPDATE_VAR).is_some() {
+        if let Some(dir) = path.parent() {
+            _ = fs::create_dir_all(dir);
+        }
+
+        fs::write(path, actual)
+            .unwrap_or_else(|e| panic!("could not write snapshot {}: {e}", path.display()));
+
+        return;
+    }
+
+    if !path.exists() {
+        panic!(
+            "snapshot missing ({}), rerun with {UPDATE_VAR}=1",
+            path.display()
+        );
+    }
+
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("could not read snapshot {}: {e}", path.display()));
+
+    assert_same(&expected, actual, &path.display().to_string());
+}
+
+fn assert_same(expected: &str, actual: &str, name: &str) {
+    if expected != actual {
+        panic!(
+            "snapshot mismatch ({name}), rerun with {UPDATE_VAR}=1 to accept\n{}",
+            diff(expected, actual)
+        );
+    }
+}
+
+// Line by line diff, lines are quoted so trailing spaces show
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => _ = writeln!(out, "  {i:>3} |{e}|"),
+            (e, a) => {
+                if let Some(e) = e {
+                    _ = writeln!(out, "- {i:>3} |{e}|");
+                }
+
+                if let Some(a) = a {
+                    _ = writeln!(out, "+ {i:>3} |{a}|");
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{style::color::Color, unit::Point};
+
+    fn snapshot() -> Snapshot {
+        let mut buf = Buffer::new(3, 2);
+        buf.go(Point::new(1, 1));
+        buf.print("ab");
+        buf.set_style(Color::Red);
+        buf.go(Point::new(2, 2));
+        buf.print("c");
+        Snapshot::new(buf)
+    }
+
+    #[test]
+    fn text_grid() {
+        let snap = snapshot();
+        assert_eq!(snap.lines(), ["ab ", " c "]);
+        assert_eq!(snap.text(), "ab \n c ");
+        assert_eq!(snap.char_at(1, 1), Some('c'));
+        assert_eq!(snap.char_at(3, 0), None);
+    }
+
+    #[test]
+    fn styles_in_order() {
+        let snap = snapshot();
+        let red = snap.style_at(1, 1).unwrap();
+        assert_eq!(red.fg, Color::Red);
+        assert_eq!(snap.styles(), [Style::new(), red]);
+        assert!(snap.style_map().starts_with("aaa\naba\na: "));
+    }
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        let d = diff("ab\ncd", "ab\nce\nf");
+        assert_eq!(d, "    0 |ab|\n-   1 |cd|\n+   1 |ce|\n+   2 |f|\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "snapshot mismatch")]
+    fn assert_text_mismatch() {
+        snapshot().assert_text("ab \n c");
+    }
+
+    #[test]
+    #[should_panic(expected = "snapshot missing")]
+    fn missing_snapshot_fails() {
+        snapshot().assert_snapshot(std::env::temp_dir().join("brannon-missing.snap"));
+    }
+}
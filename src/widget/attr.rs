@@ -9,6 +9,15 @@ use crate::{
     unit::*,
 };
 
+// What has to be drawn again since the widget was last drawn. Layout changes can move or
+// uncover other widgets, so they repaint the container around the widget as well.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Dirty {
+    Clean,
+    Paint,
+    Layout,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Attr {
     pub hide: bool,
@@ -16,8 +25,21 @@ pub struct Attr {
     pub(crate) tag: String,
     pub(crate) should_fill: bool,
     pub(crate) selected: bool,
+    // Where the outline was last drawn, for hit-testing and spatial focus navigation
+    pub(crate) rect: Option<Rect>,
+    pub(crate) dirty: Dirty,
+    // Some widget inside this one is dirty
+    pub(crate) dirty_below: bool,
+
+    pub focusable: bool,
+    // Tab order, widgets with one come first in ascending order, then the rest in tree order
+    pub tab_index: Option<usize>,
 
     pub flex: bool,
+    // Size given before flex fit it to the children, so flexing again starts from it, and the
+    // size flex fit it to. Anything else in width and height was written since and is kept.
+    pub(crate) base_size: Option<(Unit, Unit)>,
+    pub(crate) fitted_size: Option<(Unit, Unit)>,
 
     pub orientation: Orientation,
 
@@ -59,7 +81,14 @@ impl Attr {
             tag: String::new(),
             should_fill: false,
             selected: false,
+            rect: None,
+            dirty: Dirty::Layout,
+            dirty_below: false,
+            focusable: false,
+            tab_index: None,
             flex: false,
+            base_size: None,
+            fitted_size: None,
             orientation: Orientation::Vertical,
             width: Unit::CoR(0),
             height: Unit::CoR(0),
@@ -106,38 +135,147 @@ impl Attr {
         self
     }
 
+    pub fn select(&mut self) -> &mut Attr {
+        if !self.selected {
+            self.mark(Dirty::Paint);
+        }
+
+        self.selected = true;
+        self
+    }
+
+    pub fn deselect(&mut self) -> &mut Attr {
+        if self.selected {
+            self.mark(Dirty::Paint);
+        }
+
+        self.selected = false;
+        self
+    }
+
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    // None until the widget has been drawn
+    pub fn rect(&self) -> Option<Rect> {
+        self.rect
+    }
+
+    pub(crate) fn mark(&mut self, dirty: Dirty) {
+        self.dirty = self.dirty.max(dirty);
+    }
+
+    // Builders mark the widget on their own, this is for changes made through the fields or
+    // the widget's own data
+    pub fn redraw(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
+        self
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty != Dirty::Clean
+    }
+
+    // Carries over what focus and drawing keep track of, for an attr built again to replace
+    // this one
+    pub(crate) fn keep_state(&mut self, old: &Attr) {
+        self.selected = old.selected;
+        self.rect = old.rect;
+        self.should_fill = old.should_fill;
+        self.dirty = old.dirty;
+        self.dirty_below = old.dirty_below;
+    }
+
+    // The attr as it was built, without that state or the size flex fit it to
+    pub(crate) fn declared(&self) -> Attr {
+        let mut attr = self.clone();
+
+        if let Some(size) = attr.base_size.take()
+            && attr.fitted_size.take() == Some((attr.width, attr.height))
+        {
+            (attr.width, attr.height) = size;
+        }
+
+        attr.selected = false;
+        attr.rect = None;
+        attr.should_fill = false;
+        attr.dirty = Dirty::Clean;
+        attr.dirty_below = false;
+        attr
+    }
+
+    // Whether going from other to this moves the widgets around it
+    pub(crate) fn layout_differs(&self, other: &Attr) -> bool {
+        let layout = |a: &Attr| {
+            (
+                a.hide,
+                a.width,
+                a.height,
+                [
+                    a.padding_top,
+                    a.padding_right,
+                    a.padding_bottom,
+                    a.padding_left,
+                ],
+            )
+        };
+
+        layout(self) != layout(other)
+    }
+
+    pub fn focusable(&mut self) -> &mut Attr {
+        self.focusable = true;
+        self
+    }
+
+    pub fn tab_index(&mut self, value: usize) -> &mut Attr {
+        self.focusable = true;
+        self.tab_index = Some(value);
+        self
+    }
+
     pub fn flex(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.flex = true;
         self
     }
 
     pub fn no_flex(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.flex = false;
         self
     }
 
     pub fn orientation(&mut self, value: Orientation) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.orientation = value;
         self
     }
 
     pub fn horizontal(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.orientation = Orientation::Horizontal;
         self
     }
 
     pub fn vertical(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.orientation = Orientation::Vertical;
         self
     }
 
     pub fn size<T: Into<Unit>>(&mut self, xvalue: T, yvalue: T) -> &mut Attr {
+        self.mark(Dirty::Layout);
+        self.base_size = None;
         self.width = xvalue.into();
         self.height = yvalue.into();
         self
     }
 
     pub fn width(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
+        self.base_size = None;
         self.width = value.into();
         self
     }
@@ -147,16 +285,22 @@ impl Attr {
     }
 
     pub fn inc_width(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
+        self.base_size = None;
         self.width += value.into();
         self
     }
 
     pub fn dec_width(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
+        self.base_size = None;
         self.width -= value.into();
         self
     }
 
     pub fn height(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
+        self.base_size = None;
         self.height = value.into();
         self
     }
@@ -166,16 +310,21 @@ impl Attr {
     }
 
     pub fn inc_height(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
+        self.base_size = None;
         self.height += value.into();
         self
     }
 
     pub fn dec_height(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
+        self.base_size = None;
         self.height -= value.into();
         self
     }
 
     pub fn paddingd<T: Into<Unit> + Copy>(&mut self, value: &[T]) -> &mut Attr {
+        self.mark(Dirty::Layout);
         match value.len() {
             1 => {
                 let p = value[0].into();
@@ -204,6 +353,7 @@ impl Attr {
     }
 
     pub fn pad(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
         let value = value.into();
         self.padding_top = value;
         self.padding_right = value;
@@ -213,6 +363,7 @@ impl Attr {
     }
 
     pub fn paddingx(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
         let value = value.into();
         self.padding_right = value;
         self.padding_left = value;
@@ -220,6 +371,7 @@ impl Attr {
     }
 
     pub fn paddingy(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
         let value = value.into();
         self.padding_top = value;
         self.padding_bottom = value;
@@ -227,38 +379,45 @@ impl Attr {
     }
 
     pub fn padding_top(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
         self.padding_top = value.into();
         self
     }
 
     pub fn padding_right(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
         self.padding_right = value.into();
         self
     }
 
     pub fn padding_bottom(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
         self.padding_bottom = value.into();
         self
     }
 
     pub fn padding_left(&mut self, value: impl Into<Unit>) -> &mut Attr {
+        self.mark(Dirty::Layout);
         self.padding_left = value.into();
         self
     }
 
     pub fn align(&mut self, xvalue: AlignX, yvalue: AlignY) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.alignx = xvalue;
         self.aligny = yvalue;
         self
     }
 
     pub fn center(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.alignx = AlignX::Center;
         self.aligny = AlignY::Center;
         self
     }
 
     pub fn center_all(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.alignx = AlignX::Center;
         self.aligny = AlignY::Center;
         self.title_align = AlignX::Center;
@@ -267,136 +426,162 @@ impl Attr {
     }
 
     pub fn alignx(&mut self, value: AlignX) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.alignx = value;
         self
     }
 
     pub fn centerx(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.alignx = AlignX::Center;
         self
     }
 
     pub fn aligny(&mut self, value: AlignY) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.aligny = value;
         self
     }
 
     pub fn centery(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.aligny = AlignY::Center;
         self
     }
 
     pub fn fill(&mut self, value: ColorBG) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.fill = value;
         self.border_fill = value;
         self
     }
 
     pub fn fg(&mut self, value: Color) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.text_color = value;
         self.border_color = value;
         self
     }
 
     pub fn text(&mut self, style_value: TextStyle, color_value: Color) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.text_style = style_value;
         self.text_color = color_value;
         self
     }
 
     pub fn text_style(&mut self, value: TextStyle) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.text_style = value;
         self
     }
 
     pub fn bold(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.text_style = TextStyle::Bold;
         self
     }
 
     pub fn italic(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.text_style = TextStyle::Italic;
         self
     }
 
     pub fn underline(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.text_style = TextStyle::Underline;
         self
     }
 
     pub fn text_color(&mut self, value: Color) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.text_color = value;
         self
     }
 
     pub fn arc(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.arc = true;
         self
     }
 
     pub fn square(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.arc = false;
         self
     }
 
     pub fn show_border(&mut self, value: bool) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.hide_border = !value;
         self
     }
 
     pub fn hide_border(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.hide_border = true;
         self
     }
 
     pub fn border_color(&mut self, value: Color) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.border_color = value;
         self
     }
 
     pub fn border_fill(&mut self, value: ColorBG) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.border_fill = value;
         self
     }
 
     pub fn title(&mut self, value: impl Into<String>) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.title = value.into();
         self.hide_title = false;
         self
     }
 
     pub fn show_title(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.hide_title = false;
         self
     }
 
     pub fn hide_title(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.hide_title = true;
         self
     }
 
     pub fn title_align(&mut self, value: AlignX) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.title_align = value;
         self
     }
 
     pub fn binds(&mut self, value: impl Into<Binds>) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.binds = value.into();
         self.hide_binds = false;
         self
     }
 
     pub fn show_binds(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.hide_binds = false;
         self
     }
 
     pub fn hide_binds(&mut self) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.hide_binds = true;
         self
     }
 
     pub fn binds_align(&mut self, value: AlignX) -> &mut Attr {
+        self.mark(Dirty::Paint);
         self.binds_align = value;
         self
     }
@@ -408,6 +593,7 @@ impl Default for Attr {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -587,6 +773,20 @@ mod tests {
         assert!(!a.hide_binds);
     }
 
+    #[test]
+    fn focus_controls() {
+        let mut a = Attr::new();
+        assert!(!a.focusable);
+        a.focusable();
+        assert!(a.focusable);
+        assert_eq!(a.tab_index, None);
+
+        let mut b = Attr::new();
+        b.tab_index(2);
+        assert!(b.focusable);
+        assert_eq!(b.tab_index, Some(2));
+    }
+
     #[test]
     fn tag_and_arc_square() {
         let mut a = Attr::new();
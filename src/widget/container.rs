@@ -1,4 +1,5 @@
 use crate::{
+    draw::buffer::Buffer,
     impl_widget_base,
     panel::Panel,
     panel_shared,
@@ -33,7 +34,65 @@ impl Widget for Container {
         Some(self)
     }
 
-    fn render(&mut self, anchor: Point) {
-        self.render_children((anchor, 1, 1).into());
+    fn render(&mut self, anchor: Point, buf: &mut Buffer) {
+        self.render_children((anchor, 1, 1).into(), buf);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{panel::frame::Frame, testing::render_frame, unit::Unit, widget::label::Label};
+
+    #[test]
+    fn horizontal_children() {
+        let mut container = Container::new(Attr::new().horizontal().hide_border().wrap());
+        container.addm(vec![
+            Label::new("a", Attr::new().size(3usize, 3usize).wrap()),
+            Label::new("b", Attr::new().size(3usize, 3usize).wrap()),
+        ]);
+
+        let mut frame = Frame::new(Attr::new().hide_border().wrap());
+        frame.add(container);
+
+        let snap = render_frame(&mut frame, 12, 8);
+        assert_eq!(snap.lines()[4], "    ┌─┐  ┌─┐");
+        assert_eq!(snap.lines()[5], "    │a│  │b│");
+        assert_eq!(snap.lines()[6], "    └─┘  └─┘");
+    }
+
+    #[test]
+    fn vertical_children() {
+        let mut container = Container::new(Attr::new().hide_border().wrap());
+        container.addm(vec![
+            Label::new("a", Attr::new().size(3usize, 3usize).wrap()),
+            Label::new("b", Attr::new().size(3usize, 3usize).wrap()),
+        ]);
+
+        let mut frame = Frame::new(Attr::new().hide_border().wrap());
+        frame.add(container);
+
+        let snap = render_frame(&mut frame, 8, 12);
+        let column: String = (4..12).map(|y| snap.char_at(5, y).unwrap()).collect();
+        assert_eq!(column, "─a─  ─b─");
+    }
+
+    #[test]
+    fn written_size_is_kept() {
+        let label = || Label::new("a", Attr::new().size(3usize, 3usize).wrap());
+        let mut container = Container::new(None);
+        container.add(label());
+        assert_eq!(container.style().width.calc(), 7);
+
+        container.style_mut().width = Unit::CoR(20);
+        container.add(label());
+        assert_eq!(container.style().width.calc(), 20);
+
+        // Still grows to fit, and goes back to the written size after
+        container.add(Label::new("a", Attr::new().size(30usize, 3usize).wrap()));
+        assert_eq!(container.style().width.calc(), 34);
+        container.split_mut().1.pop();
+        container.relayout();
+        assert_eq!(container.style().width.calc(), 20);
     }
 }
@@ -1,10 +1,7 @@
 use crate::{
-    draw::cursor,
-    impl_widget_base, printf,
-    style::{
-        align::{AlignX, AlignY},
-        set_style,
-    },
+    draw::buffer::Buffer,
+    impl_widget_base,
+    style::align::{AlignX, AlignY},
     unit::{Point, Unit},
     widget::{Widget, attr::Attr},
 };
@@ -28,9 +25,9 @@ impl Label {
 impl_widget_base!(Label);
 
 impl Widget for Label {
-    fn render(&mut self, anchor: Point) {
-        set_style(self.attr.text_style);
-        set_style(self.attr.text_color);
+    fn render(&mut self, anchor: Point, buf: &mut Buffer) {
+        buf.set_style(self.attr.text_style);
+        buf.set_style(self.attr.text_color);
 
         let mut lines: Vec<String> = self
             .text
@@ -60,9 +57,64 @@ impl Widget for Label {
                     AlignX::Right => self.attr.width.calc() - line.len() - 2,
                 })
             }
-            cursor::go(pos);
-            printf!("{}", line);
+            buf.go(pos);
+            buf.print(line);
             pos.y += Unit::CoR(1);
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        panel::{Panel, frame::Frame},
+        style::{color::Color, text::TextStyle},
+        testing::render_frame,
+    };
+
+    fn frame_with(label: Box<Label>) -> Frame {
+        let mut frame = Frame::new(Attr::new().hide_border().wrap());
+        frame.add(label);
+        frame
+    }
+
+    #[test]
+    fn wraps_and_truncates() {
+        let mut frame = frame_with(Label::new(
+            "abcdefghij",
+            Attr::new().size(6usize, 4usize).wrap(),
+        ));
+
+        render_frame(&mut frame, 9, 7).assert_text(
+            "         \n         \n  ┌────┐ \n  │abcd│ \n  │efgh│ \n  └────┘ \n         ",
+        );
+    }
+
+    #[test]
+    fn centered() {
+        let mut frame = frame_with(Label::new(
+            "ab",
+            Attr::new().size(8usize, 5usize).center().wrap(),
+        ));
+
+        let snap = render_frame(&mut frame, 11, 8);
+        assert_eq!(snap.lines()[4], "  │  ab  │ ");
+    }
+
+    #[test]
+    fn text_style() {
+        let mut frame = frame_with(Label::new(
+            "a",
+            Attr::new()
+                .size(3usize, 3usize)
+                .bold()
+                .text_color(Color::Red)
+                .wrap(),
+        ));
+
+        let style = render_frame(&mut frame, 6, 6).style_at(3, 3).unwrap();
+        assert_eq!(style.fg, Color::Red);
+        assert!(style.has(TextStyle::Bold));
+    }
+}
@@ -4,10 +4,10 @@ pub mod label;
 pub mod progress_bar;
 
 use crate::{
-    draw::{cursor, draw_binds, draw_box, draw_title},
+    draw::{buffer::Buffer, draw_binds, draw_box, draw_title},
+    input::Input,
     panel::Panel,
-    printf,
-    style::{line::Line, set_style, text::TextStyle},
+    style::{line::Line, text::TextStyle},
     unit::Point,
 };
 use attr::Attr;
@@ -27,43 +27,60 @@ pub trait WidgetBase: Any + 'static {
     fn weq(&self, other: &dyn Widget) -> bool;
 }
 
+// Mouse inputs are routed from the outermost container down to the widget under the pointer,
+// then back up through the same containers
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Phase {
+    Capture,
+    Target,
+    Bubble,
+}
+
 pub trait Widget: WidgetBase {
     fn set_style(&mut self, attr: Attr) {
         (*self.style_mut()) = attr;
+        self.style_mut().mark(attr::Dirty::Layout);
     }
 
     fn as_panel(&mut self) -> Option<&mut dyn Panel> {
         None
     }
 
-    fn render(&mut self, anchor: Point);
+    fn render(&mut self, anchor: Point, buf: &mut Buffer);
+
+    // Mouse input routed through or to this widget. Returning true stops it there, it goes no
+    // further and doesn't reach run.
+    fn handle(&mut self, _input: &Input, _phase: Phase) -> bool {
+        false
+    }
 
-    fn outline(&self, anchor: Point) {
-        self.fill(anchor);
-        self.border(anchor);
+    fn outline(&self, anchor: Point, buf: &mut Buffer) {
+        self.fill(anchor, buf);
+        self.border(anchor, buf);
     }
 
-    fn fill(&self, anchor: Point) {
+    fn fill(&self, anchor: Point, buf: &mut Buffer) {
         if self.style().should_fill {
             let h = self.style().height.calc();
             let s = " ".repeat(self.style().width.calc());
             let mut pos = anchor;
 
-            set_style(self.style().fill);
+            buf.set_style(self.style().fill);
 
             for _ in 0..h {
-                cursor::go(pos);
-                printf!("{}", s);
+                buf.go(pos);
+                buf.print(&s);
                 pos.y += 1.into();
             }
         }
     }
 
-    fn border(&self, anchor: Point) {
-        set_style(self.style().border_color);
-        set_style(self.style().border_fill);
+    fn border(&self, anchor: Point, buf: &mut Buffer) {
+        buf.set_style(self.style().border_color);
+        buf.set_style(self.style().border_fill);
 
         draw_box(
+            buf,
             anchor,
             self.style(),
             if self.style().selected {
@@ -73,10 +90,10 @@ pub trait Widget: WidgetBase {
             },
         );
 
-        set_style(TextStyle::Bold);
+        buf.set_style(TextStyle::Bold);
 
-        draw_title(anchor, self.style());
-        draw_binds(anchor, self.style());
+        draw_title(buf, anchor, self.style());
+        draw_binds(buf, anchor, self.style());
     }
 }
 
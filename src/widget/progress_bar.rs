@@ -1,9 +1,12 @@
 use crate::{
-    draw::cursor,
-    impl_widget_base, printf,
-    style::{color::ColorBG, set_style},
+    draw::buffer::Buffer,
+    impl_widget_base,
+    style::color::ColorBG,
     unit::{Point, Unit},
-    widget::{Widget, attr::Attr},
+    widget::{
+        Widget,
+        attr::{Attr, Dirty},
+    },
 };
 use std::any::Any;
 
@@ -24,6 +27,8 @@ impl ProgressBar {
     }
 
     pub fn progress(&mut self, value: usize) {
+        self.attr.mark(Dirty::Paint);
+
         if value > 100 {
             self.progress = 100;
         } else {
@@ -32,6 +37,8 @@ impl ProgressBar {
     }
 
     pub fn increment(&mut self) {
+        self.attr.mark(Dirty::Paint);
+
         self.progress += 1;
         if self.progress > 100 {
             self.progress = 100;
@@ -39,6 +46,8 @@ impl ProgressBar {
     }
 
     pub fn decrement(&mut self) {
+        self.attr.mark(Dirty::Paint);
+
         self.progress -= 1;
         if self.progress > 100 {
             self.progress = 100;
@@ -46,6 +55,8 @@ impl ProgressBar {
     }
 
     pub fn inc_progress(&mut self, value: usize) {
+        self.attr.mark(Dirty::Paint);
+
         self.progress += value;
         if self.progress > 100 {
             self.progress = 100;
@@ -53,6 +64,8 @@ impl ProgressBar {
     }
 
     pub fn dec_progress(&mut self, value: usize) {
+        self.attr.mark(Dirty::Paint);
+
         if value > self.progress {
             self.progress = 0;
         } else {
@@ -61,10 +74,14 @@ impl ProgressBar {
     }
 
     pub fn reset(&mut self) {
+        self.attr.mark(Dirty::Paint);
+
         self.progress = 0;
     }
 
     pub fn bar_color(&mut self, value: ColorBG) {
+        self.attr.mark(Dirty::Paint);
+
         self.bar_color = value;
     }
 }
@@ -72,27 +89,77 @@ impl ProgressBar {
 impl_widget_base!(ProgressBar);
 
 impl Widget for ProgressBar {
-    fn render(&mut self, anchor: Point) {
-        set_style(self.bar_color);
+    fn render(&mut self, anchor: Point, buf: &mut Buffer) {
+        buf.set_style(self.bar_color);
 
         let mut pos: Point = (anchor, Unit::CoR(1), Unit::CoR(1)).into();
-        cursor::go(pos);
-        printf!(
-            "{}",
-            " ".repeat(
-                (((self.progress as f32) / 100.0) * ((self.attr.width.calc() - 2) as f32)) as usize
-            )
-        );
+        buf.go(pos);
+        buf.print(" ".repeat(
+            (((self.progress as f32) / 100.0) * ((self.attr.width.calc() - 2) as f32)) as usize,
+        ));
 
-        set_style(self.attr.text_color);
-        set_style(self.attr.text_style);
+        buf.set_style(self.attr.text_color);
+        buf.set_style(self.attr.text_style);
 
         if self.progress < 53 {
-            set_style(self.attr.fill);
+            buf.set_style(self.attr.fill);
         }
 
         pos.x += Unit::CoR(self.attr.width.calc() / 2);
-        cursor::go(pos);
-        printf!("{}%", self.progress);
+        buf.go(pos);
+        buf.print(format!("{}%", self.progress));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        panel::{Panel, frame::Frame},
+        testing::render_frame,
+    };
+
+    fn frame_with(bar: Box<ProgressBar>) -> Frame {
+        let mut frame = Frame::new(Attr::new().hide_border().wrap());
+        frame.add(bar);
+        frame
+    }
+
+    #[test]
+    fn clamps_progress() {
+        let mut bar = ProgressBar::new(ColorBG::Green, None);
+        bar.progress(150);
+        assert_eq!(bar.progress, 100);
+        bar.dec_progress(120);
+        assert_eq!(bar.progress, 0);
+        bar.inc_progress(30);
+        bar.increment();
+        assert_eq!(bar.progress, 31);
+    }
+
+    #[test]
+    fn half_full() {
+        let mut bar = ProgressBar::new(ColorBG::Green, Attr::new().width(12usize).wrap());
+        bar.progress(50);
+
+        let snap = render_frame(&mut frame_with(bar), 15, 6);
+        snap.assert_text(
+            "               \n               \n  ┌──────────┐ \n  │      50% │ \n  └──────────┘ \n               ",
+        );
+
+        // Five of the ten inner cells take the bar color
+        let filled = (3..13)
+            .filter(|x| snap.style_at(*x, 3).unwrap().bg == ColorBG::Green)
+            .count();
+        assert_eq!(filled, 5);
+    }
+
+    #[test]
+    fn empty() {
+        let bar = ProgressBar::new(ColorBG::Green, Attr::new().width(8usize).wrap());
+        let snap = render_frame(&mut frame_with(bar), 11, 6);
+
+        assert_eq!(snap.lines()[3], "  │    0%│ ");
+        assert!((3..9).all(|x| snap.style_at(x, 3).unwrap().bg != ColorBG::Green));
     }
 }